@@ -0,0 +1,91 @@
+//! Allocations and time spent parsing a generated document, run with `cargo bench`
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    hint::black_box,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+use json2env::{JsonParser, ParseOptions};
+use serde_json::{json, Value};
+
+const RUNS: u32 = 50;
+
+/// System allocator counting the allocations made through it
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Configuration of many services, mixing nested objects, arrays and scalars
+fn document() -> Value {
+    let services = (0..1_000)
+        .map(|index| {
+            json!({
+                "name": format!("service-{index}"),
+                "database": { "host": "localhost", "port": 5432, "pool": { "min": 1, "max": 10 } },
+                "replicas": [{ "zone": "a", "weight": 1 }, { "zone": "b", "weight": 2 }],
+                "tags": ["web", "internal", "v2"],
+                "enabled": index % 2 == 0
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({ "services": services })
+}
+
+/// Print the allocations of a single run and the average time of `RUNS` runs
+fn measure(name: &str, mut run: impl FnMut() -> usize) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let variables = black_box(run());
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        black_box(run());
+    }
+    let elapsed = start.elapsed() / RUNS;
+
+    println!(
+        "{name:<22} {variables:>6} variables {allocations:>8} allocations {:>8} µs",
+        elapsed.as_micros()
+    );
+}
+
+fn main() {
+    let json = document();
+
+    for (name, options) in [
+        ("joined", ParseOptions::default()),
+        (
+            "enumerated",
+            ParseOptions::builder().enumerate_array(true).build(),
+        ),
+    ] {
+        let parser = JsonParser::new(options);
+
+        measure(&format!("parse/{name}"), || parser.parse(&json).len());
+        measure(&format!("parse_iter/{name}"), || {
+            parser.parse_iter(&json).count()
+        });
+    }
+}
@@ -0,0 +1,26 @@
+#ifndef JSON2ENV_H
+#define JSON2ENV_H
+
+#include <stdbool.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/*
+ * Convert the JSON document to the content of a `.env` file.
+ *
+ * Null separators select the defaults, `__` and `,`. Returns NULL if the document is not valid
+ * JSON or a string is not valid UTF-8, the result must be released with `json2env_free`.
+ */
+char *json2env_parse(const char *json, const char *key_separator, const char *array_separator,
+                     bool enumerate_array);
+
+/* Release a string returned by `json2env_parse`, NULL is ignored. */
+void json2env_free(char *output);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
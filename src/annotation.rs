@@ -0,0 +1,181 @@
+use std::io::Write;
+
+use serde_json::{Map, Value};
+
+use crate::{EnvVar, Error, JsonParser};
+
+/// Metadata of an object held by a key starting with the annotation prefix, like
+/// `"x-description"`, which is not meant to become a variable
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    /// JSON pointer of the annotated object
+    pub pointer: String,
+    /// Key of the annotation without the prefix
+    pub name: String,
+    pub value: Value,
+}
+
+/// Remove the keys starting with the prefix from every object of the document, returning them as
+/// annotations in document order
+pub fn strip(json: &mut Value, prefix: &str) -> Vec<Annotation> {
+    let mut annotations = Vec::new();
+    let mut stack = vec![(json, String::new())];
+
+    while let Some((value, pointer)) = stack.pop() {
+        let children = match value {
+            Value::Object(map) => {
+                // the map is rebuilt, removing keys would not keep the order of the others
+                for (key, value) in std::mem::take(map) {
+                    match key.strip_prefix(prefix) {
+                        Some(name) => annotations.push(Annotation {
+                            pointer: pointer.clone(),
+                            name: name.to_owned(),
+                            value,
+                        }),
+                        None => {
+                            map.insert(key, value);
+                        }
+                    }
+                }
+
+                children(map, &pointer)
+            }
+            Value::Array(array) => array
+                .iter_mut()
+                .enumerate()
+                .map(|(index, value)| (value, format!("{pointer}/{index}")))
+                .collect(),
+            _ => continue,
+        };
+
+        // children are pushed in reverse to be visited in document order
+        stack.extend(children.into_iter().rev());
+    }
+
+    annotations
+}
+
+fn children<'a>(map: &'a mut Map<String, Value>, pointer: &str) -> Vec<(&'a mut Value, String)> {
+    map.iter_mut()
+        .map(|(key, value)| {
+            let mut pointer = pointer.to_owned();
+            JsonParser::push_pointer_segment(&mut pointer, key);
+            (value, pointer)
+        })
+        .collect()
+}
+
+/// Write the variables as a `.env` file, preceding the first variable of each annotated object
+/// with `# name: value` comments.
+///
+/// Annotations of objects which produced no variable are not written.
+pub fn render(
+    vars: &[EnvVar],
+    annotations: &[Annotation],
+    writer: &mut dyn Write,
+) -> Result<(), Error> {
+    let within = |pointer: &str, var: &EnvVar| {
+        var.source_pointer().is_some_and(|source| {
+            source == pointer
+                || source
+                    .strip_prefix(pointer)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+    };
+
+    let firsts = annotations
+        .iter()
+        .map(|annotation| vars.iter().position(|var| within(&annotation.pointer, var)))
+        .collect::<Vec<_>>();
+
+    for (index, var) in vars.iter().enumerate() {
+        if index > 0 {
+            writer.write_all(b"\n")?;
+        }
+
+        let annotated = annotations
+            .iter()
+            .zip(firsts.iter())
+            .filter(|(_, first)| **first == Some(index));
+
+        for (annotation, _) in annotated {
+            let text = match annotation.value {
+                Value::String(ref text) => text.clone(),
+                ref value => value.to_string(),
+            };
+
+            let mut lines = text.lines();
+            let first = lines.next().unwrap_or_default();
+            writeln!(writer, "# {name}: {first}", name = annotation.name)?;
+            for line in lines {
+                writeln!(writer, "# {line}")?;
+            }
+        }
+
+        writer.write_all(var.render()?.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{render, strip, Annotation};
+    use crate::{JsonParser, ParseOptions};
+
+    #[test]
+    fn strip_should_remove_annotations_in_document_order() {
+        // ARRANGE
+        let mut json = json!({
+            "x-owner": "platform",
+            "db": { "x-description": "Primary database", "host": "localhost", "port": 5432 },
+            "servers": [{ "x-note": 1, "name": "web" }]
+        });
+
+        // ACT
+        let annotations = strip(&mut json, "x-");
+
+        // ASSERT
+        let annotation = |pointer: &str, name: &str, value| Annotation {
+            pointer: pointer.to_owned(),
+            name: name.to_owned(),
+            value,
+        };
+        assert_eq!(
+            annotations,
+            vec![
+                annotation("", "owner", json!("platform")),
+                annotation("/db", "description", json!("Primary database")),
+                annotation("/servers/0", "note", json!(1)),
+            ]
+        );
+        assert_eq!(
+            json,
+            json!({ "db": { "host": "localhost", "port": 5432 }, "servers": [{ "name": "web" }] })
+        )
+    }
+
+    #[test]
+    fn render_should_comment_the_first_variable_of_annotated_objects() {
+        // ARRANGE
+        let mut json = json!({
+            "app": "web",
+            "db": { "x-description": "Primary\ndatabase", "host": "localhost", "port": 5432 },
+            "cache": { "x-description": "Unused" }
+        });
+        let annotations = strip(&mut json, "x-");
+        let vars = JsonParser::new(ParseOptions::default()).parse(&json);
+
+        // ACT
+        let mut output = Vec::new();
+        render(&vars, &annotations, &mut output).unwrap();
+
+        // ASSERT
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "app=\"web\"\n# description: Primary\n# database\ndb__host=\"localhost\"\ndb__port=5432"
+        )
+    }
+}
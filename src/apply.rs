@@ -0,0 +1,92 @@
+use crate::{EnvVar, Error};
+
+/// Set the variables in the environment of the current process, keeping the ones already defined
+/// like dotenv loaders do.
+///
+/// # Safety rules
+///
+/// Modifying the environment is not thread-safe on most Unix platforms: call this at startup,
+/// before spawning threads or running code that may read the environment concurrently.
+///
+/// Fails without setting anything if a key is empty or contains `=` or NUL, or a value contains
+/// NUL, as the platform would reject them.
+pub fn apply(vars: &[EnvVar]) -> Result<(), Error> {
+    set(vars, false)
+}
+
+/// Like [`apply`], but variables already defined are overridden
+pub fn apply_overriding(vars: &[EnvVar]) -> Result<(), Error> {
+    set(vars, true)
+}
+
+fn set(vars: &[EnvVar], overriding: bool) -> Result<(), Error> {
+    let pairs = vars
+        .iter()
+        .map(<(String, String)>::from)
+        .collect::<Vec<_>>();
+
+    for (key, value) in pairs.iter() {
+        if key.is_empty() || key.contains(['=', '\0']) {
+            return Err(Error::Validation(format!(
+                "`{key}` is not a valid variable name"
+            )));
+        }
+
+        if value.contains('\0') {
+            return Err(Error::Validation(format!(
+                "The value of `{key}` contains a NUL character"
+            )));
+        }
+    }
+
+    for (key, value) in pairs {
+        if overriding || std::env::var_os(&key).is_none() {
+            std::env::set_var(key, value);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply, apply_overriding};
+    use crate::EnvVar;
+
+    #[test]
+    fn apply_should_keep_defined_variables_unless_overriding() {
+        // ARRANGE
+        std::env::set_var("JSON2ENV_APPLY_DEFINED", "process");
+        let vars = vec![
+            EnvVar::new("JSON2ENV_APPLY_DEFINED", "json"),
+            EnvVar::new("JSON2ENV_APPLY_NEW", 1),
+        ];
+
+        // ACT
+        apply(&vars).unwrap();
+        let kept = std::env::var("JSON2ENV_APPLY_DEFINED").unwrap();
+        apply_overriding(&vars).unwrap();
+        let overridden = std::env::var("JSON2ENV_APPLY_DEFINED").unwrap();
+
+        // ASSERT
+        assert_eq!(kept, "process");
+        assert_eq!(overridden, "json");
+        assert_eq!(std::env::var("JSON2ENV_APPLY_NEW").unwrap(), "1")
+    }
+
+    #[test]
+    fn apply_should_reject_invalid_keys() {
+        // ARRANGE
+        let vars = vec![
+            EnvVar::new("JSON2ENV_APPLY_VALID", "x"),
+            EnvVar::new("A=B", "x"),
+        ];
+
+        // ACT
+        let result = apply(&vars);
+
+        // ASSERT
+        assert!(result.is_err());
+        assert!(std::env::var_os("JSON2ENV_APPLY_VALID").is_none())
+    }
+}
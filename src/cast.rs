@@ -0,0 +1,173 @@
+use std::str::FromStr;
+
+use serde_json::{Number, Value};
+
+use crate::Error;
+
+/// Type a value of the document is coerced to before it is converted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cast {
+    /// Integer, from a number without fraction or a string holding one
+    Int,
+    /// Number, from a number or a string holding one
+    Float,
+    /// `true` or `false`, from `true`, `false`, `yes`, `no`, `1` or `0` in any case
+    Bool,
+    /// String, from any value, nested ones become their JSON text
+    String,
+}
+
+impl FromStr for Cast {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "int" => Ok(Cast::Int),
+            "float" => Ok(Cast::Float),
+            "bool" => Ok(Cast::Bool),
+            "string" => Ok(Cast::String),
+            _ => Err(Error::InvalidOptions(format!(
+                "`{name}` is not a type, expected `int`, `float`, `bool` or `string`"
+            ))),
+        }
+    }
+}
+
+impl Cast {
+    fn name(&self) -> &'static str {
+        match self {
+            Cast::Int => "int",
+            Cast::Float => "float",
+            Cast::Bool => "bool",
+            Cast::String => "string",
+        }
+    }
+
+    /// The value as the type, `None` if it cannot be represented by it
+    pub fn apply(&self, value: &Value) -> Option<Value> {
+        match (self, value) {
+            (Cast::Int, Value::Number(number)) if number.is_i64() || number.is_u64() => {
+                Some(value.clone())
+            }
+            (Cast::Int, Value::Number(number)) => number
+                .as_f64()
+                .filter(|float| float.fract() == 0.0 && float.abs() < i64::MAX as f64)
+                .map(|float| Value::from(float as i64)),
+            (Cast::Int, Value::String(string)) => {
+                let string = string.trim();
+                string
+                    .parse::<i64>()
+                    .map(Value::from)
+                    .or_else(|_| string.parse::<u64>().map(Value::from))
+                    .ok()
+            }
+            (Cast::Float, Value::Number(_)) => Some(value.clone()),
+            (Cast::Float, Value::String(string)) => string
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .and_then(Number::from_f64)
+                .map(Value::Number),
+            (Cast::Bool, Value::Bool(_)) => Some(value.clone()),
+            (Cast::Bool, Value::Number(number)) => match number.as_u64() {
+                Some(0) => Some(Value::Bool(false)),
+                Some(1) => Some(Value::Bool(true)),
+                _ => None,
+            },
+            (Cast::Bool, Value::String(string)) => match string.trim().to_lowercase().as_str() {
+                "true" | "yes" | "1" => Some(Value::Bool(true)),
+                "false" | "no" | "0" => Some(Value::Bool(false)),
+                _ => None,
+            },
+            (Cast::String, Value::String(_)) => Some(value.clone()),
+            (Cast::String, value) => Some(Value::String(value.to_string())),
+            _ => None,
+        }
+    }
+}
+
+/// Coerce the values at the JSON pointers of the document to their type, in order.
+///
+/// Pointers missing from the document are ignored, fails if a value cannot be represented by its
+/// type.
+pub fn apply(json: &mut Value, casts: &[(String, Cast)]) -> Result<(), Error> {
+    for (pointer, cast) in casts {
+        let Some(value) = json.pointer_mut(pointer) else {
+            continue;
+        };
+
+        *value = cast.apply(value).ok_or_else(|| {
+            let (cast, value) = (cast.name(), value.to_string());
+            Error::InvalidInput(format!("`{pointer}` cannot be cast to {cast}: {value}").into())
+        })?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{apply, Cast};
+    use crate::Error;
+
+    #[test]
+    fn apply_should_coerce_values_to_their_type() {
+        // ARRANGE
+        let mut json = json!({
+            "port": "5432",
+            "ratio": "0.5",
+            "debug": "Yes",
+            "id": 42,
+            "server": { "timeout": 30.0 },
+            "hosts": ["a", "b"]
+        });
+        let casts = [
+            ("/port".to_owned(), Cast::Int),
+            ("/ratio".to_owned(), Cast::Float),
+            ("/debug".to_owned(), Cast::Bool),
+            ("/id".to_owned(), Cast::String),
+            ("/server/timeout".to_owned(), Cast::Int),
+            ("/hosts".to_owned(), Cast::String),
+            ("/missing".to_owned(), Cast::Int),
+        ];
+
+        // ACT
+        apply(&mut json, &casts).unwrap();
+
+        // ASSERT
+        let expected = json!({
+            "port": 5432,
+            "ratio": 0.5,
+            "debug": true,
+            "id": "42",
+            "server": { "timeout": 30 },
+            "hosts": r#"["a","b"]"#
+        });
+        assert_eq!(json, expected)
+    }
+
+    #[test]
+    fn apply_should_fail_on_impossible_casts() {
+        // ARRANGE
+        let mut json = json!({ "port": "http", "flag": 2, "list": [1] });
+        let casts = [
+            ("/port", Cast::Int),
+            ("/flag", Cast::Bool),
+            ("/list", Cast::Float),
+        ];
+
+        // ACT
+        let result = casts.map(|(pointer, cast)| apply(&mut json, &[(pointer.to_owned(), cast)]));
+
+        // ASSERT
+        assert!(result
+            .iter()
+            .all(|result| matches!(result, Err(Error::InvalidInput(_)))));
+        assert_eq!(
+            result[0].as_ref().unwrap_err().to_string(),
+            r#"`/port` cannot be cast to int: "http""#
+        )
+    }
+}
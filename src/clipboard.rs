@@ -0,0 +1,41 @@
+use json2env::Error;
+
+/// Text held by the system clipboard
+pub fn read() -> Result<String, Error> {
+    #[cfg(feature = "clipboard")]
+    {
+        let text = arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_text());
+        text.map_err(|error| unavailable("read", error))
+    }
+
+    #[cfg(not(feature = "clipboard"))]
+    Err(disabled())
+}
+
+/// Replace the content of the system clipboard with the text.
+///
+/// On Linux the text may be lost when the process exits, unless a clipboard manager keeps it.
+pub fn write(text: String) -> Result<(), Error> {
+    #[cfg(feature = "clipboard")]
+    {
+        let written = arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text));
+        written.map_err(|error| unavailable("write to", error))
+    }
+
+    #[cfg(not(feature = "clipboard"))]
+    {
+        let _ = text;
+        Err(disabled())
+    }
+}
+
+#[cfg(feature = "clipboard")]
+fn unavailable(action: &str, error: arboard::Error) -> Error {
+    log::error!("Could not {action} the clipboard");
+    std::io::Error::other(error).into()
+}
+
+#[cfg(not(feature = "clipboard"))]
+fn disabled() -> Error {
+    Error::InvalidOptions("The clipboard requires the `clipboard` feature".to_owned())
+}
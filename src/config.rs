@@ -0,0 +1,60 @@
+use std::{collections::BTreeMap, path::Path};
+
+use json2env::Error;
+use serde::Deserialize;
+
+use crate::{FormatArg, KeyCase, Quoting, TargetArg};
+
+/// Files looked up in the working directory when no configuration is given explicitly
+const FILE_NAMES: [&str; 2] = ["json2env.toml", ".json2envrc"];
+
+/// Defaults for the command line options, overridden by the flags given explicitly
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    pub key_separator: Option<String>,
+    pub array_separator: Option<String>,
+    pub enumerate_array: Option<bool>,
+    pub key_case: Option<KeyCase>,
+    pub root_key: Option<String>,
+    pub target: Option<TargetArg>,
+    pub sanitize: Option<bool>,
+    /// `POINTER=TYPE` rules, like `--cast`
+    pub cast: Option<Vec<String>>,
+    pub cast_file: Option<String>,
+    pub exclude_value: Option<String>,
+    pub deny_excluded: Option<bool>,
+    pub annotations: Option<bool>,
+    pub annotation_prefix: Option<String>,
+    pub infer_types: Option<bool>,
+    pub format: Option<FormatArg>,
+    pub quoting: Option<Quoting>,
+    /// Named bundles of the options above, selected with `--preset`
+    #[serde(default)]
+    pub presets: BTreeMap<String, Config>,
+}
+
+impl Config {
+    /// Load the configuration from the given file, or from the first one found in the working directory
+    pub fn load(path: Option<&str>) -> Result<Self, Error> {
+        let path = match path {
+            Some(path) => path,
+            None => match FILE_NAMES
+                .into_iter()
+                .find(|name| Path::new(name).is_file())
+            {
+                Some(path) => path,
+                None => return Ok(Self::default()),
+            },
+        };
+
+        let content = std::fs::read_to_string(path)
+            .inspect_err(|_| log::error!("Could not read `{path}`"))?;
+
+        let config = toml::from_str(&content)
+            .inspect_err(|_| log::error!("`{path}` is not a valid configuration file"))
+            .map_err(|error| Error::InvalidOptions(error.to_string()))?;
+
+        Ok(config)
+    }
+}
@@ -0,0 +1,418 @@
+use std::{fmt::Display, str::FromStr};
+
+use serde::{
+    de::{self, value::StringDeserializer, DeserializeOwned, IntoDeserializer, Visitor},
+    forward_to_deserialize_any, Deserializer,
+};
+use serde_json::{Map, Value};
+
+use crate::{
+    unflatten::{strip_prefix, unflatten, UnflattenOptions},
+    EnvVar, Error, ParseOptions,
+};
+
+/// Load a typed value from variables, the inverse of [`crate::ser::to_vars`].
+///
+/// Keys are split on the key separator like [`unflatten`] does, leaves are read as the type
+/// requested by the target: numbers and booleans are parsed, sequences are read from enumerated
+/// keys or split on the array separator, and `null` is read as `None`.
+pub fn from_vars<T: DeserializeOwned>(vars: &[EnvVar], options: &ParseOptions) -> Result<T, Error> {
+    from_pairs(vars.iter().map(Into::into).collect(), options)
+}
+
+/// Load a typed value from the variables of the current process whose key starts with `prefix`
+pub fn from_env<T: DeserializeOwned>(prefix: &str, options: &ParseOptions) -> Result<T, Error> {
+    // variables whose key or value is not valid UTF-8 cannot belong to the value
+    let vars = std::env::vars_os()
+        .filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?)));
+
+    from_pairs(strip_prefix(vars, prefix), options)
+}
+
+fn from_pairs<T: DeserializeOwned>(
+    pairs: Vec<(String, String)>,
+    options: &ParseOptions,
+) -> Result<T, Error> {
+    let unflatten_options = UnflattenOptions::new(options.key_separator.clone(), false)
+        .index_brackets(options.index_brackets)
+        .index_start(options.index_start)
+        .index_width(options.index_width);
+    let value = unflatten(&pairs, &unflatten_options)?;
+
+    T::deserialize(EnvDeserializer::new(value, options))
+}
+
+/// Deserializer over a document rebuilt from variables, where every leaf is a string
+pub struct EnvDeserializer<'a> {
+    value: Value,
+    options: &'a ParseOptions,
+}
+
+impl<'a> EnvDeserializer<'a> {
+    pub fn new(value: Value, options: &'a ParseOptions) -> Self {
+        Self { value, options }
+    }
+}
+
+fn parse<T>(value: &str, kind: &str) -> Result<T, Error>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    value.parse().map_err(|error| {
+        Error::InvalidInput(format!("`{value}` is not a valid {kind}: {error}").into())
+    })
+}
+
+/// Parse string leaves as the requested scalar, anything else is handed to `deserialize_any`
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident($kind:ty)),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                match self.value {
+                    Value::String(ref value) => visitor.$visit(parse::<$kind>(value, stringify!($kind))?),
+                    _ => self.deserialize_any(visitor),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for EnvDeserializer<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            Value::String(value) => visitor.visit_string(value),
+            Value::Array(items) => visitor.visit_seq(Seq {
+                items: items.into_iter(),
+                options: self.options,
+            }),
+            Value::Object(entries) => visitor.visit_map(Entries::new(entries, self.options)),
+            value => value.deserialize_any(visitor).map_err(Error::from),
+        }
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool(bool),
+        deserialize_i8 => visit_i8(i8),
+        deserialize_i16 => visit_i16(i16),
+        deserialize_i32 => visit_i32(i32),
+        deserialize_i64 => visit_i64(i64),
+        deserialize_u8 => visit_u8(u8),
+        deserialize_u16 => visit_u16(u16),
+        deserialize_u32 => visit_u32(u32),
+        deserialize_u64 => visit_u64(u64),
+        deserialize_f32 => visit_f32(f32),
+        deserialize_f64 => visit_f64(f64),
+        deserialize_char => visit_char(char),
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            // `null` values are rendered as is
+            Value::String(ref value) if value == "null" => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            // arrays of scalars are joined when not enumerated
+            Value::String(ref value) => {
+                let items = match value.is_empty() {
+                    true => vec![],
+                    false => value
+                        .split(self.options.array_separator.as_str())
+                        .map(|item| Value::String(item.to_owned()))
+                        .collect(),
+                };
+
+                visitor.visit_seq(Seq {
+                    items: items.into_iter(),
+                    options: self.options,
+                })
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.value {
+            Value::String(variant) => visitor.visit_enum(Enum {
+                variant,
+                value: None,
+                options: self.options,
+            }),
+            // externally tagged like JSON: `{ "variant": value }`
+            Value::Object(entries) if entries.len() == 1 => {
+                let (variant, value) = entries.into_iter().next().expect("object has one entry");
+                visitor.visit_enum(Enum {
+                    variant,
+                    value: Some(value),
+                    options: self.options,
+                })
+            }
+            _ => Err(de::Error::custom("expected an enum variant")),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        str string bytes byte_buf map struct identifier
+    }
+}
+
+struct Seq<'a> {
+    items: std::vec::IntoIter<Value>,
+    options: &'a ParseOptions,
+}
+
+impl<'de> de::SeqAccess<'de> for Seq<'_> {
+    type Error = Error;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        self.items
+            .next()
+            .map(|item| seed.deserialize(EnvDeserializer::new(item, self.options)))
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+struct Entries<'a> {
+    entries: serde_json::map::IntoIter,
+    value: Option<Value>,
+    options: &'a ParseOptions,
+}
+
+impl<'a> Entries<'a> {
+    fn new(entries: Map<String, Value>, options: &'a ParseOptions) -> Self {
+        Self {
+            entries: entries.into_iter(),
+            value: None,
+            options,
+        }
+    }
+}
+
+impl<'de> de::MapAccess<'de> for Entries<'_> {
+    type Error = Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        let Some((key, value)) = self.entries.next() else {
+            return Ok(None);
+        };
+
+        self.value = Some(value);
+        // keys go through the same parsing as leaves, to read maps with numeric keys
+        seed.deserialize(EnvDeserializer::new(Value::String(key), self.options))
+            .map(Some)
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let value = self
+            .value
+            .take()
+            .expect("next_value_seed called before next_key_seed");
+
+        seed.deserialize(EnvDeserializer::new(value, self.options))
+    }
+}
+
+struct Enum<'a> {
+    variant: String,
+    value: Option<Value>,
+    options: &'a ParseOptions,
+}
+
+impl<'de, 'a> de::EnumAccess<'de> for Enum<'a> {
+    type Error = Error;
+    type Variant = Variant<'a>;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Variant<'a>), Error> {
+        let variant: StringDeserializer<Error> = self.variant.into_deserializer();
+        let variant = seed.deserialize(variant)?;
+
+        Ok((
+            variant,
+            Variant {
+                value: self.value,
+                options: self.options,
+            },
+        ))
+    }
+}
+
+struct Variant<'a> {
+    value: Option<Value>,
+    options: &'a ParseOptions,
+}
+
+impl<'de> de::VariantAccess<'de> for Variant<'_> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        match self.value {
+            None => Ok(()),
+            Some(_) => Err(de::Error::custom("expected a unit variant")),
+        }
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        let value = self
+            .value
+            .ok_or_else(|| <Error as de::Error>::custom("expected a newtype variant"))?;
+        seed.deserialize(EnvDeserializer::new(value, self.options))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        let value = self
+            .value
+            .ok_or_else(|| <Error as de::Error>::custom("expected a tuple variant"))?;
+        EnvDeserializer::new(value, self.options).deserialize_seq(visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let value = self
+            .value
+            .ok_or_else(|| <Error as de::Error>::custom("expected a struct variant"))?;
+        EnvDeserializer::new(value, self.options).deserialize_any(visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde::{Deserialize, Serialize};
+
+    use super::from_vars;
+    use crate::{ser::to_vars, EnvVar, ParseOptions};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Database {
+        host: String,
+        port: u16,
+        password: Option<String>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Mode {
+        Fast,
+        Limit(u32),
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Config {
+        db: Database,
+        hosts: Vec<String>,
+        ports: Vec<u16>,
+        modes: (Mode, Mode),
+        labels: BTreeMap<u8, bool>,
+    }
+
+    #[test]
+    fn from_vars_should_read_back_serialized_values() {
+        // ARRANGE
+        let config = Config {
+            db: Database {
+                host: "localhost".to_owned(),
+                port: 5432,
+                password: None,
+            },
+            hosts: vec!["a".to_owned(), "b".to_owned()],
+            ports: vec![80, 443],
+            modes: (Mode::Fast, Mode::Limit(10)),
+            labels: BTreeMap::from([(1, true)]),
+        };
+        let joined = ParseOptions::default();
+        let enumerated = ParseOptions::builder().enumerate_array(true).build();
+
+        for options in [joined, enumerated] {
+            let vars = to_vars(&config, &options).unwrap();
+
+            // ACT
+            let result: Config = from_vars(&vars, &options).unwrap();
+
+            // ASSERT
+            assert_eq!(result, config)
+        }
+    }
+
+    #[test]
+    fn from_vars_should_fail_on_unparsable_values() {
+        // ARRANGE
+        let vars = vec![
+            EnvVar::new("host", "localhost"),
+            EnvVar::new("port", "http"),
+        ];
+
+        // ACT
+        let result = from_vars::<BTreeMap<String, u16>>(&vars, &ParseOptions::default());
+
+        // ASSERT
+        assert!(result.is_err())
+    }
+}
@@ -0,0 +1,242 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::EnvVar;
+
+const MASK: &str = "***";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Added {
+        key: String,
+        value: String,
+    },
+    Removed {
+        key: String,
+        value: String,
+    },
+    Changed {
+        key: String,
+        old: String,
+        new: String,
+    },
+}
+
+impl Change {
+    pub fn key(&self) -> &str {
+        match self {
+            Change::Added { key, .. }
+            | Change::Removed { key, .. }
+            | Change::Changed { key, .. } => key,
+        }
+    }
+
+    /// Replace the values of the change so that it can be displayed without leaking them
+    pub fn masked(self) -> Self {
+        self.masked_with(MASK)
+    }
+
+    /// Like [`Change::masked`], with the given replacement of the values
+    pub fn masked_with(self, mask: &str) -> Self {
+        match self {
+            Change::Added { key, .. } => Change::Added {
+                key,
+                value: mask.to_owned(),
+            },
+            Change::Removed { key, .. } => Change::Removed {
+                key,
+                value: mask.to_owned(),
+            },
+            Change::Changed { key, .. } => Change::Changed {
+                key,
+                old: mask.to_owned(),
+                new: mask.to_owned(),
+            },
+        }
+    }
+}
+
+impl Display for Change {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Change::Added { key, value } => write!(fmt, "+ {key}={value}"),
+            Change::Removed { key, value } => write!(fmt, "- {key}={value}"),
+            Change::Changed { key, old, new } => write!(fmt, "~ {key}={old} -> {new}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnvDiff {
+    changes: Vec<Change>,
+}
+
+impl EnvDiff {
+    /// Compare two sets of variables, reporting what has to change to go from `old` to `new`.
+    ///
+    /// Added and changed keys are listed in the order of `new`, removed keys in the order of `old`.
+    pub fn new(old: &[(String, String)], new: &[(String, String)]) -> Self {
+        let find = |pairs: &[(String, String)], key: &str| {
+            pairs
+                .iter()
+                .rev()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value.clone())
+        };
+
+        let mut changes = Vec::new();
+
+        for (index, (key, value)) in new.iter().enumerate() {
+            // only the last definition of a duplicated key is effective
+            if new[index + 1..].iter().any(|(name, _)| name == key) {
+                continue;
+            }
+
+            match find(old, key) {
+                None => changes.push(Change::Added {
+                    key: key.clone(),
+                    value: value.clone(),
+                }),
+                Some(old) if old != *value => changes.push(Change::Changed {
+                    key: key.clone(),
+                    old,
+                    new: value.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+
+        for (index, (key, value)) in old.iter().enumerate() {
+            let is_last = !old[index + 1..].iter().any(|(name, _)| name == key);
+
+            if is_last && find(new, key).is_none() {
+                changes.push(Change::Removed {
+                    key: key.clone(),
+                    value: value.clone(),
+                });
+            }
+        }
+
+        Self { changes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    pub fn into_changes(self) -> Vec<Change> {
+        self.changes
+    }
+}
+
+/// Keep the variables which are not in the baseline with the same value, in their order.
+///
+/// Keys only defined by the baseline cannot be represented and are not reported.
+pub fn changed(vars: Vec<EnvVar>, baseline: &[(String, String)]) -> Vec<EnvVar> {
+    // only the last definition of a duplicated key is effective
+    let baseline = baseline
+        .iter()
+        .map(|(key, value)| (key.as_str(), value.as_str()))
+        .collect::<HashMap<_, _>>();
+
+    vars.into_iter()
+        .filter(|var| {
+            let (key, value) = <(String, String)>::from(var);
+            baseline.get(key.as_str()) != Some(&value.as_str())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{changed, Change, EnvDiff};
+    use crate::EnvVar;
+
+    fn pair(key: &str, value: &str) -> (String, String) {
+        (key.to_owned(), value.to_owned())
+    }
+
+    #[test]
+    fn changed_should_keep_new_and_different_variables() {
+        // ARRANGE
+        let vars = vec![
+            EnvVar::new("A", 1),
+            EnvVar::new("B", "2"),
+            EnvVar::new("C", true),
+        ];
+        let baseline = vec![
+            pair("A", "0"),
+            pair("A", "1"),
+            pair("B", "3"),
+            pair("D", "4"),
+        ];
+
+        // ACT
+        let result = changed(vars, &baseline);
+
+        // ASSERT
+        assert_eq!(result, vec![EnvVar::new("B", "2"), EnvVar::new("C", true)])
+    }
+
+    #[test]
+    fn diff_should_be_empty_for_equal_variables() {
+        // ARRANGE
+        let old = vec![pair("A", "1"), pair("B", "2")];
+        let new = vec![pair("B", "2"), pair("A", "1")];
+
+        // ACT
+        let diff = EnvDiff::new(&old, &new);
+
+        // ASSERT
+        assert!(diff.is_empty())
+    }
+
+    #[test]
+    fn diff_should_report_added_removed_and_changed_keys() {
+        // ARRANGE
+        let old = vec![pair("A", "1"), pair("B", "2")];
+        let new = vec![pair("B", "3"), pair("C", "4")];
+
+        // ACT
+        let diff = EnvDiff::new(&old, &new);
+
+        // ASSERT
+        assert_eq!(
+            diff.into_changes(),
+            vec![
+                Change::Changed {
+                    key: "B".to_owned(),
+                    old: "2".to_owned(),
+                    new: "3".to_owned()
+                },
+                Change::Added {
+                    key: "C".to_owned(),
+                    value: "4".to_owned()
+                },
+                Change::Removed {
+                    key: "A".to_owned(),
+                    value: "1".to_owned()
+                },
+            ]
+        )
+    }
+
+    #[test]
+    fn masked_change_should_hide_values() {
+        // ARRANGE
+        let change = Change::Changed {
+            key: "SECRET".to_owned(),
+            old: "hunter2".to_owned(),
+            new: "hunter3".to_owned(),
+        };
+
+        // ACT
+        let result = change.masked().to_string();
+
+        // ASSERT
+        assert_eq!(result, "~ SECRET=*** -> ***")
+    }
+}
@@ -0,0 +1,235 @@
+use std::ops::Range;
+
+use crate::EnvVar;
+
+/// Single `KEY=value` assignment of a `.env` file
+struct Entry {
+    key: String,
+    value: String,
+    exported: bool,
+    /// Byte range of the lines holding the assignment, excluding the final line break
+    span: Range<usize>,
+}
+
+/// Parse the contents of a `.env` file into its key-value pairs, in file order.
+///
+/// Blank lines, `#` comments and a leading `export` are ignored. Double-quoted values may span
+/// multiple lines and unescape `\"`, single-quoted values are taken literally and unquoted values
+/// end at the first ` #` inline comment.
+pub fn parse(input: &str) -> Vec<(String, String)> {
+    entries(input)
+        .into_iter()
+        .map(|entry| (entry.key, entry.value))
+        .collect()
+}
+
+/// Merge the variables into the contents of an existing `.env` file.
+///
+/// Assignments of keys present in `vars` are replaced in place and their duplicates removed,
+/// while unrelated lines and comments are preserved. Variables not yet in the file are appended.
+pub fn update(input: &str, vars: &[EnvVar]) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut written = vec![false; vars.len()];
+    let mut position = 0;
+
+    for entry in entries(input) {
+        let Some(index) = vars.iter().rposition(|var| var.0 == entry.key) else {
+            continue;
+        };
+
+        output.push_str(&input[position..entry.span.start]);
+        position = entry.span.end;
+
+        if written[index] {
+            // drop the line break of the removed duplicate as well
+            position = (position + 1).min(input.len());
+            continue;
+        }
+
+        if entry.exported {
+            output.push_str("export ");
+        }
+
+        output.push_str(&vars[index].to_string());
+        written[index] = true;
+    }
+
+    output.push_str(&input[position..]);
+
+    for (index, var) in vars.iter().enumerate() {
+        // only the last definition of a duplicated key is effective
+        let is_last = !vars[index + 1..].iter().any(|other| other.0 == var.0);
+
+        if is_last && !written[index] {
+            if !output.is_empty() && !output.ends_with('\n') {
+                output.push('\n');
+            }
+
+            output.push_str(&var.to_string());
+            output.push('\n');
+        }
+    }
+
+    output
+}
+
+fn entries(input: &str) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let mut position = 0;
+
+    while position < input.len() {
+        let end = line_end(input, position);
+        let line = &input[position..end];
+        let line_start = position;
+        position = (end + 1).min(input.len());
+
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        let exported = trimmed.starts_with("export ");
+        let trimmed = trimmed.strip_prefix("export ").unwrap_or(trimmed);
+        let Some((key, value)) = trimmed.split_once('=') else {
+            continue;
+        };
+
+        let key = key.trim().to_owned();
+        let value = value.trim_start();
+        let mut span = line_start..end;
+
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                // quoted values may continue past the end of the current line
+                let value_start = line_start + line.trim_end().len() - value.len() + 1;
+                let (value, consumed) = parse_quoted(&input[value_start..], quote);
+                span.end = line_end(input, value_start + consumed);
+                position = (span.end + 1).min(input.len());
+                value
+            }
+            _ => match value.find(" #") {
+                Some(index) => value[..index].trim_end().to_owned(),
+                None => value.trim_end().to_owned(),
+            },
+        };
+
+        entries.push(Entry {
+            key,
+            value,
+            exported,
+            span,
+        });
+    }
+
+    entries
+}
+
+fn line_end(input: &str, from: usize) -> usize {
+    input[from..]
+        .find('\n')
+        .map_or(input.len(), |index| from + index)
+}
+
+/// Parse a quoted value starting right after the opening quote.
+/// Returns the unescaped value and the number of bytes consumed, including the closing quote.
+fn parse_quoted(input: &str, quote: char) -> (String, usize) {
+    let mut value = String::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some((index, char)) = chars.next() {
+        match char {
+            '\\' if quote == '"' && chars.peek().is_some_and(|&(_, next)| next == '"') => {
+                chars.next();
+                value.push('"');
+            }
+            char if char == quote => return (value, index + char.len_utf8()),
+            char => value.push(char),
+        }
+    }
+
+    (value, input.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{parse, update};
+    use crate::EnvVar;
+
+    fn pair(key: &str, value: &str) -> (String, String) {
+        (key.to_owned(), value.to_owned())
+    }
+
+    #[test]
+    fn parse_should_skip_comments_and_blank_lines() {
+        // ARRANGE
+        let input = "# comment\n\nKEY=value\n";
+
+        // ACT
+        let result = parse(input);
+
+        // ASSERT
+        assert_eq!(result, vec![pair("KEY", "value")])
+    }
+
+    #[test]
+    fn parse_should_unescape_double_quoted_values() {
+        // ARRANGE
+        let input = r#"export KEY="say \"hi\"""#;
+
+        // ACT
+        let result = parse(input);
+
+        // ASSERT
+        assert_eq!(result, vec![pair("KEY", r#"say "hi""#)])
+    }
+
+    #[test]
+    fn parse_should_read_multiline_quoted_values() {
+        // ARRANGE
+        let input = "FIRST=\"line1\nline2\"\nSECOND='a # b'\nTHIRD=plain # comment";
+
+        // ACT
+        let result = parse(input);
+
+        // ASSERT
+        assert_eq!(
+            result,
+            vec![
+                pair("FIRST", "line1\nline2"),
+                pair("SECOND", "a # b"),
+                pair("THIRD", "plain")
+            ]
+        )
+    }
+
+    #[test]
+    fn update_should_replace_managed_keys_and_preserve_other_lines() {
+        // ARRANGE
+        let input = "# local\nexport A=\"old\nvalue\"\nOTHER=1\nA=dup\n";
+        let vars = vec![
+            EnvVar::new("A".to_owned(), json!("new")),
+            EnvVar::new("B".to_owned(), json!(2)),
+        ];
+
+        // ACT
+        let result = update(input, &vars);
+
+        // ASSERT
+        assert_eq!(result, "# local\nexport A=\"new\"\nOTHER=1\nB=2\n")
+    }
+
+    #[test]
+    fn update_should_be_idempotent() {
+        // ARRANGE
+        let vars = vec![EnvVar::new("A".to_owned(), json!("value"))];
+        let first = update("OTHER=1", &vars);
+
+        // ACT
+        let second = update(&first, &vars);
+
+        // ASSERT
+        assert_eq!(second, first)
+    }
+}
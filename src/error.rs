@@ -0,0 +1,107 @@
+/// Failure classes of a conversion, each mapped to a distinct process exit code
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The input is not a valid document
+    #[error("{0}")]
+    InvalidInput(Box<dyn std::error::Error + Send + Sync>),
+    /// The input is nested deeper than the configured limit
+    #[error("`{pointer}` is nested deeper than the limit of {limit} levels")]
+    TooDeep { pointer: String, limit: usize },
+    /// The options or the configuration are not valid
+    #[error("{0}")]
+    InvalidOptions(String),
+    /// Reading or writing a file or stream failed
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// The generated variables do not satisfy the requested constraints
+    #[error("{0}")]
+    Validation(String),
+    /// More than one value produced the same key
+    #[error("`{key}` is defined more than once")]
+    Collision { key: String },
+    /// The value cannot be represented in the output format
+    #[error("`{key}` cannot be rendered: {reason}")]
+    Render { key: String, reason: String },
+    /// The generated variables differ from the expected ones
+    #[error("The variables differ")]
+    Drift,
+}
+
+impl Error {
+    /// Exit code reported by the binary
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Error::Drift => 1,
+            Error::InvalidOptions(_) => 2,
+            Error::InvalidInput(_) | Error::TooDeep { .. } => 3,
+            Error::Io(_) => 4,
+            Error::Validation(_) | Error::Collision { .. } => 5,
+            Error::Render { .. } => 6,
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        match error.classify() {
+            serde_json::error::Category::Io => Error::Io(error.into()),
+            _ => Error::InvalidInput(error.into()),
+        }
+    }
+}
+
+/// Failures raised by the `Deserialize` implementation of the value being loaded
+impl serde::de::Error for Error {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Error::InvalidInput(msg.to_string().into())
+    }
+}
+
+/// Failures raised by the `Serialize` implementation of the value being flattened
+impl serde::ser::Error for Error {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Error::InvalidInput(msg.to_string().into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Error;
+
+    #[test]
+    fn exit_codes_should_be_distinct() {
+        // ARRANGE
+        let errors = [
+            Error::Drift,
+            Error::InvalidInput("invalid".into()),
+            Error::Io(std::io::ErrorKind::NotFound.into()),
+            Error::InvalidOptions("invalid".to_owned()),
+            Error::Validation("invalid".to_owned()),
+            Error::Render {
+                key: "key".to_owned(),
+                reason: "invalid".to_owned(),
+            },
+        ];
+
+        // ACT
+        let mut codes = errors.iter().map(Error::exit_code).collect::<Vec<_>>();
+        codes.sort();
+        codes.dedup();
+
+        // ASSERT
+        assert_eq!(codes.len(), errors.len());
+        assert!(!codes.contains(&0))
+    }
+
+    #[test]
+    fn json_syntax_error_should_be_invalid_input() {
+        // ARRANGE
+        let error = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+
+        // ACT
+        let result = Error::from(error);
+
+        // ASSERT
+        assert_eq!(result.exit_code(), 3)
+    }
+}
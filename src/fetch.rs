@@ -0,0 +1,133 @@
+use std::{future::Future, io, path::PathBuf, pin::Pin, sync::Arc, time::Duration};
+
+use serde_json::Value;
+use tokio::task::JoinSet;
+
+use crate::Error;
+
+/// Pending retrieval of the content of a source
+pub type Fetch<'a> = Pin<Box<dyn Future<Output = Result<String, Error>> + Send + 'a>>;
+
+/// Location an input document is retrieved from, implemented by remote backends
+pub trait Source: Send + Sync {
+    /// Name of the source shown in errors, like a path or URL
+    fn name(&self) -> String;
+
+    fn fetch(&self) -> Fetch<'_>;
+}
+
+/// Document read from the local file system
+#[derive(Debug, Clone)]
+pub struct FileSource {
+    path: PathBuf,
+}
+
+impl FileSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl Source for FileSource {
+    fn name(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn fetch(&self) -> Fetch<'_> {
+        Box::pin(async move { Ok(tokio::fs::read_to_string(&self.path).await?) })
+    }
+}
+
+/// Retrieve and parse the documents of all the sources concurrently, in the order of the sources.
+///
+/// Fails with the first error, or with a [`io::ErrorKind::TimedOut`] error if a source takes longer
+/// than `timeout`.
+pub async fn fetch_all(
+    sources: Vec<Arc<dyn Source>>,
+    timeout: Duration,
+) -> Result<Vec<Value>, Error> {
+    let mut documents = vec![Value::Null; sources.len()];
+    let mut tasks = JoinSet::new();
+
+    for (index, source) in sources.into_iter().enumerate() {
+        tasks.spawn(async move {
+            let content = tokio::time::timeout(timeout, source.fetch())
+                .await
+                .map_err(|_| {
+                    let message = format!("Fetching `{}` timed out", source.name());
+                    Error::Io(io::Error::new(io::ErrorKind::TimedOut, message))
+                })??;
+
+            let json = crate::input::parse(&content)?;
+            Ok::<_, Error>((index, json))
+        });
+    }
+
+    while let Some(result) = tasks.join_next().await {
+        let (index, json) =
+            result.unwrap_or_else(|error| std::panic::resume_unwind(error.into_panic()))?;
+        documents[index] = json;
+    }
+
+    Ok(documents)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use serde_json::json;
+
+    use super::{fetch_all, Fetch, FileSource, Source};
+    use crate::Error;
+
+    struct Slow;
+
+    impl Source for Slow {
+        fn name(&self) -> String {
+            "slow".to_owned()
+        }
+
+        fn fetch(&self) -> Fetch<'_> {
+            Box::pin(async {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                Ok("{}".to_owned())
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn fetch_all_should_return_documents_in_source_order() {
+        // ARRANGE
+        let dir = std::env::temp_dir();
+        let first = dir.join("json2env_fetch_first.json");
+        let second = dir.join("json2env_fetch_second.json");
+        std::fs::write(&first, r#"{ "a": 1 }"#).unwrap();
+        std::fs::write(&second, r#"{ "b": 2 }"#).unwrap();
+
+        let sources: Vec<Arc<dyn Source>> = vec![
+            Arc::new(FileSource::new(&first)),
+            Arc::new(FileSource::new(&second)),
+        ];
+
+        // ACT
+        let result = fetch_all(sources, Duration::from_secs(5)).await.unwrap();
+
+        // ASSERT
+        assert_eq!(result, vec![json!({ "a": 1 }), json!({ "b": 2 })])
+    }
+
+    #[tokio::test]
+    async fn fetch_all_should_fail_on_timeout() {
+        // ARRANGE
+        let sources: Vec<Arc<dyn Source>> = vec![Arc::new(Slow)];
+
+        // ACT
+        let result = fetch_all(sources, Duration::from_millis(10)).await;
+
+        // ASSERT
+        assert!(
+            matches!(result, Err(Error::Io(error)) if error.kind() == std::io::ErrorKind::TimedOut)
+        )
+    }
+}
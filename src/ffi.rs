@@ -0,0 +1,116 @@
+use std::{
+    ffi::{c_char, CStr, CString},
+    ptr,
+};
+
+use crate::{
+    render::{self, Format},
+    Error, JsonParser, ParseOptions,
+};
+
+/// Convert the JSON document to the content of a `.env` file.
+///
+/// Null separators select the defaults of the command line tool, `__` and `,`. Returns null if
+/// the document is not valid JSON or a string is not valid UTF-8, the returned string must be
+/// released with [`json2env_free`].
+///
+/// # Safety
+///
+/// Non-null pointers must point to NUL terminated strings valid for the duration of the call.
+#[no_mangle]
+pub unsafe extern "C" fn json2env_parse(
+    json: *const c_char,
+    key_separator: *const c_char,
+    array_separator: *const c_char,
+    enumerate_array: bool,
+) -> *mut c_char {
+    let string = |pointer: *const c_char| match pointer.is_null() {
+        true => Ok(None),
+        false => CStr::from_ptr(pointer).to_str().map(Some),
+    };
+
+    let (Ok(Some(json)), Ok(key_separator), Ok(array_separator)) =
+        (string(json), string(key_separator), string(array_separator))
+    else {
+        return ptr::null_mut();
+    };
+
+    let mut options = ParseOptions::default();
+    if let Some(separator) = key_separator {
+        options.key_separator = separator.to_owned();
+    }
+    if let Some(separator) = array_separator {
+        options.array_separator = separator.to_owned();
+    }
+    options.enumerate_array = enumerate_array;
+
+    match convert(json, options).map(CString::new) {
+        Ok(Ok(output)) => output.into_raw(),
+        // values containing NUL cannot be returned as a C string
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Release a string returned by [`json2env_parse`], null pointers are ignored.
+///
+/// # Safety
+///
+/// The pointer must have been returned by [`json2env_parse`] and not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn json2env_free(output: *mut c_char) {
+    if !output.is_null() {
+        drop(CString::from_raw(output));
+    }
+}
+
+fn convert(json: &str, options: ParseOptions) -> Result<String, Error> {
+    let json = crate::input::parse(json)?;
+    let vars = JsonParser::new(options).parse_owned(json);
+
+    let mut buffer = Vec::new();
+    render::render_to(&vars, &Format::Dotenv, &mut buffer)?;
+
+    Ok(String::from_utf8(buffer).expect("rendered variables are valid UTF-8"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        ffi::{CStr, CString},
+        ptr,
+    };
+
+    use super::{json2env_free, json2env_parse};
+
+    #[test]
+    fn json2env_parse_should_return_dotenv_content() {
+        // ARRANGE
+        let json = CString::new(r#"{ "db": { "port": 5432 } }"#).unwrap();
+        let separator = CString::new("_").unwrap();
+
+        // ACT
+        let output =
+            unsafe { json2env_parse(json.as_ptr(), separator.as_ptr(), ptr::null(), false) };
+
+        // ASSERT
+        let result = unsafe { CStr::from_ptr(output) }
+            .to_str()
+            .unwrap()
+            .to_owned();
+        unsafe { json2env_free(output) };
+
+        assert_eq!(result, "db_port=5432")
+    }
+
+    #[test]
+    fn json2env_parse_should_return_null_for_invalid_json() {
+        // ARRANGE
+        let json = CString::new("{").unwrap();
+
+        // ACT
+        let output = unsafe { json2env_parse(json.as_ptr(), ptr::null(), ptr::null(), false) };
+
+        // ASSERT
+        assert!(output.is_null())
+    }
+}
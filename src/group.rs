@@ -0,0 +1,56 @@
+use crate::EnvVar;
+
+/// Split the variables by their top-level key, the part of the key before the first `separator`.
+///
+/// Groups are listed in order of first appearance. Top-level values, whose key has no separator,
+/// are grouped under an empty name.
+pub fn group_by_top_level_key<'a>(
+    vars: &'a [EnvVar],
+    separator: &str,
+) -> Vec<(&'a str, Vec<&'a EnvVar>)> {
+    let mut groups: Vec<(&str, Vec<&EnvVar>)> = Vec::new();
+
+    for var in vars {
+        let name = match separator.is_empty() {
+            true => "",
+            false => var.0.split_once(separator).map_or("", |(name, _)| name),
+        };
+
+        match groups.iter_mut().find(|(group, _)| *group == name) {
+            Some((_, members)) => members.push(var),
+            None => groups.push((name, vec![var])),
+        }
+    }
+
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::group_by_top_level_key;
+    use crate::EnvVar;
+
+    #[test]
+    fn group_should_split_variables_by_first_key_segment() {
+        // ARRANGE
+        let vars = vec![
+            EnvVar::new("db__host".to_owned(), json!("localhost")),
+            EnvVar::new("name".to_owned(), json!("app")),
+            EnvVar::new("log__level".to_owned(), json!("info")),
+            EnvVar::new("db__port".to_owned(), json!(5432)),
+        ];
+
+        // ACT
+        let groups = group_by_top_level_key(&vars, "__");
+
+        // ASSERT
+        let names = groups
+            .iter()
+            .map(|(name, members)| (*name, members.len()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, vec![("db", 2), ("", 1), ("log", 1)])
+    }
+}
@@ -0,0 +1,361 @@
+use std::io::Read;
+
+use serde_json::Value;
+
+use crate::Error;
+
+pub(crate) const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+const UTF16_LE_BOM: &[u8] = b"\xFF\xFE";
+const UTF16_BE_BOM: &[u8] = b"\xFE\xFF";
+
+/// Text encodings of the input documents, see [`decode`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl Encoding {
+    /// Encoding of the bytes of a document, from its byte order mark if any.
+    ///
+    /// Documents without one are recognized by the NUL bytes next to their first ASCII character,
+    /// like in RFC 4627, and are UTF-8 otherwise.
+    pub fn detect(bytes: &[u8]) -> Self {
+        match bytes {
+            [0xFF, 0xFE, ..] => Encoding::Utf16Le,
+            [0xFE, 0xFF, ..] => Encoding::Utf16Be,
+            [first, 0, ..] if *first != 0 => Encoding::Utf16Le,
+            [0, second, ..] if *second != 0 => Encoding::Utf16Be,
+            _ => Encoding::Utf8,
+        }
+    }
+}
+
+/// Text of a document read as bytes, without its byte order mark.
+///
+/// UTF-16 documents, as saved by some Windows editors, are transcoded. Fails naming the offset of
+/// the first invalid sequence, counted in bytes from the start of the document.
+pub fn decode(bytes: Vec<u8>) -> Result<String, Error> {
+    decode_with(bytes, false)
+}
+
+/// Like [`decode`], but invalid sequences are replaced by `U+FFFD` instead of failing
+pub fn decode_lossy(bytes: Vec<u8>) -> String {
+    decode_with(bytes, true).expect("lossy decoding cannot fail")
+}
+
+fn decode_with(mut bytes: Vec<u8>, lossy: bool) -> Result<String, Error> {
+    let invalid = |encoding, offset| {
+        let message = format!("The input is not valid {encoding} at byte {offset}");
+        Err(Error::InvalidInput(message.into()))
+    };
+
+    let encoding = Encoding::detect(&bytes);
+    if encoding == Encoding::Utf8 {
+        let bom = match bytes.starts_with(UTF8_BOM) {
+            true => UTF8_BOM.len(),
+            false => 0,
+        };
+        bytes.drain(..bom);
+
+        return match String::from_utf8(bytes) {
+            Ok(text) => Ok(text),
+            Err(error) if lossy => Ok(String::from_utf8_lossy(error.as_bytes()).into_owned()),
+            Err(error) => invalid("UTF-8", bom + error.utf8_error().valid_up_to()),
+        };
+    }
+
+    let bom = match bytes.starts_with(UTF16_LE_BOM) || bytes.starts_with(UTF16_BE_BOM) {
+        true => UTF16_LE_BOM.len(),
+        false => 0,
+    };
+
+    let pairs = bytes[bom..].chunks_exact(2);
+    if !pairs.remainder().is_empty() && !lossy {
+        return invalid("UTF-16", bytes.len() - 1);
+    }
+
+    // a trailing odd byte is an incomplete sequence too
+    let incomplete = (!pairs.remainder().is_empty()).then_some(char::REPLACEMENT_CHARACTER);
+    let units = pairs.map(|pair| match encoding {
+        Encoding::Utf16Be => u16::from_be_bytes([pair[0], pair[1]]),
+        _ => u16::from_le_bytes([pair[0], pair[1]]),
+    });
+
+    let mut text = String::with_capacity(bytes.len());
+    let mut offset = bom;
+    for char in char::decode_utf16(units) {
+        match char {
+            Ok(char) => {
+                text.push(char);
+                offset += char.len_utf16() * 2;
+            }
+            Err(_) if lossy => {
+                text.push(char::REPLACEMENT_CHARACTER);
+                offset += 2;
+            }
+            Err(_) => return invalid("UTF-16", offset),
+        }
+    }
+    text.extend(incomplete);
+
+    Ok(text)
+}
+
+/// Parse the content of an input document, ignoring a leading byte order mark.
+///
+/// With the `simd` feature the document is parsed by simd-json, documents it rejects are parsed
+/// again by serde_json so that errors keep reporting their line and column.
+pub fn parse(json: &str) -> Result<Value, Error> {
+    let json = json.strip_prefix('\u{FEFF}').unwrap_or(json);
+
+    #[cfg(feature = "simd")]
+    {
+        // simd-json parses in place, the input is left untouched
+        let mut buffer = json.as_bytes().to_vec();
+        if let Ok(value) = simd_json::serde::from_slice(&mut buffer) {
+            return Ok(value);
+        }
+    }
+
+    Ok(serde_json::from_str(json)?)
+}
+
+/// JSON text of a JSONC document, as used by VS Code settings and `tsconfig.json`.
+///
+/// `//` and `/* */` comments, and commas trailing the last element of objects and arrays, are
+/// replaced by spaces so that errors keep reporting the line and column of the original document.
+pub fn strip_comments(jsonc: &str) -> String {
+    let bytes = jsonc.as_bytes();
+    let mut output = bytes.to_vec();
+    // position of the last comma outside of strings and comments, if only whitespace followed it
+    let mut comma = None;
+    let mut index = 0;
+
+    let blank = |output: &mut [u8], range: std::ops::Range<usize>| {
+        for byte in output[range].iter_mut() {
+            // line breaks are kept for the positions, multi-byte characters become spaces too
+            if !matches!(byte, b'\n' | b'\r') {
+                *byte = b' ';
+            }
+        }
+    };
+
+    while index < bytes.len() {
+        match (bytes[index], bytes.get(index + 1)) {
+            (b'"', _) => {
+                comma = None;
+                index += 1;
+                while index < bytes.len() && bytes[index] != b'"' {
+                    index += if bytes[index] == b'\\' { 2 } else { 1 };
+                }
+                index += 1;
+            }
+            (b'/', Some(b'/')) => {
+                let end = jsonc[index..]
+                    .find('\n')
+                    .map_or(bytes.len(), |end| index + end);
+                blank(&mut output, index..end);
+                index = end;
+            }
+            (b'/', Some(b'*')) => {
+                let end = jsonc[index + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |end| index + 2 + end + 2);
+                blank(&mut output, index..end);
+                index = end;
+            }
+            (b',', _) => {
+                comma = Some(index);
+                index += 1;
+            }
+            (b'}' | b']', _) => {
+                if let Some(comma) = comma.take() {
+                    output[comma] = b' ';
+                }
+                index += 1;
+            }
+            (byte, _) => {
+                if !byte.is_ascii_whitespace() {
+                    comma = None;
+                }
+                index += 1;
+            }
+        }
+    }
+
+    String::from_utf8(output).expect("only whole characters are replaced")
+}
+
+/// Documents of a stream of concatenated JSON values, like the output of `kubectl get --watch`,
+/// parsed as they are read.
+///
+/// Values may be separated by whitespace or not at all, the iteration stops at the first error.
+pub fn documents(reader: impl Read) -> impl Iterator<Item = Result<Value, Error>> {
+    let mut failed = false;
+    serde_json::Deserializer::from_reader(reader)
+        .into_iter::<Value>()
+        .map_while(move |result| {
+            (!failed).then(|| {
+                failed = result.is_err();
+                result.map_err(Error::from)
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{decode, decode_lossy, documents, parse, strip_comments};
+    use crate::Error;
+
+    #[test]
+    fn parse_should_return_the_document() {
+        // ARRANGE
+        let json =
+            r#"{ "db": { "host": "localhost", "port": 5432 }, "ratio": 0.5, "tags": ["a", null] }"#;
+
+        // ACT
+        let result = parse(json).unwrap();
+
+        // ASSERT
+        let expected = json!({
+            "db": { "host": "localhost", "port": 5432 },
+            "ratio": 0.5,
+            "tags": ["a", null]
+        });
+        assert_eq!(result, expected)
+    }
+
+    #[test]
+    fn parse_should_report_the_position_of_errors() {
+        // ARRANGE
+        let json = "{\n  \"a\": }";
+
+        // ACT
+        let result = parse(json);
+
+        // ASSERT
+        assert!(
+            matches!(result, Err(Error::InvalidInput(error)) if error.to_string().contains("line 2"))
+        )
+    }
+
+    #[test]
+    fn strip_comments_should_keep_the_positions_of_the_json() {
+        // ARRANGE
+        let jsonc = concat!(
+            "{\n",
+            "  // the \"database\"\n",
+            "  \"url\": \"http://host/*path*/\", /* inline, */\n",
+            "  \"tags\": [\"a\", \"b\",],\n",
+            "  \"città\": 1, /* à\n  */\n",
+            "}"
+        );
+
+        // ACT
+        let result = strip_comments(jsonc);
+
+        // ASSERT
+        assert_eq!(result.len(), jsonc.len());
+        assert_eq!(
+            result.lines().map(str::trim_end).collect::<Vec<_>>(),
+            [
+                "{",
+                "",
+                r#"  "url": "http://host/*path*/","#,
+                r#"  "tags": ["a", "b" ],"#,
+                r#"  "città": 1"#,
+                "",
+                "}"
+            ]
+        );
+        assert!(parse(&result).is_ok())
+    }
+
+    #[test]
+    fn documents_should_parse_concatenated_values() {
+        // ARRANGE
+        let json = "{ \"a\": 1 }\n{ \"a\": 2 }{ \"b\": [] } { \"a\": }";
+
+        // ACT
+        let result = documents(json.as_bytes()).collect::<Vec<_>>();
+
+        // ASSERT
+        assert_eq!(result.len(), 4);
+        assert_eq!(
+            result[..3]
+                .iter()
+                .map(|result| result.as_ref().unwrap())
+                .collect::<Vec<_>>(),
+            [&json!({ "a": 1 }), &json!({ "a": 2 }), &json!({ "b": [] })]
+        );
+        assert!(matches!(result[3], Err(Error::InvalidInput(_))))
+    }
+
+    #[test]
+    fn decode_should_strip_the_utf8_byte_order_mark() {
+        // ARRANGE
+        let bytes = b"\xEF\xBB\xBF{ \"a\": 1 }".to_vec();
+
+        // ACT
+        let result = decode(bytes).unwrap();
+
+        // ASSERT
+        assert_eq!(result, r#"{ "a": 1 }"#)
+    }
+
+    #[test]
+    fn decode_should_transcode_utf16() {
+        // ARRANGE
+        let json = r#"{ "città": 1 }"#;
+        let little_endian = [0xFF, 0xFE]
+            .into_iter()
+            .chain(json.encode_utf16().flat_map(u16::to_le_bytes))
+            .collect::<Vec<_>>();
+        let big_endian = json
+            .encode_utf16()
+            .flat_map(u16::to_be_bytes)
+            .collect::<Vec<_>>();
+
+        // ACT
+        let result = [decode(little_endian).unwrap(), decode(big_endian).unwrap()];
+
+        // ASSERT
+        assert_eq!(result, [json, json])
+    }
+
+    #[test]
+    fn decode_should_name_the_offset_of_invalid_sequences() {
+        // ARRANGE
+        let utf8 = b"\xEF\xBB\xBF{ \"a\": \"\xFF\" }".to_vec();
+        let utf16 = [0xFF, 0xFE, b'{', 0, 0x00, 0xD8, b'}', 0].to_vec();
+
+        // ACT
+        let result = [decode(utf8), decode(utf16)].map(|result| result.unwrap_err().to_string());
+
+        // ASSERT
+        assert_eq!(
+            result,
+            [
+                "The input is not valid UTF-8 at byte 11",
+                "The input is not valid UTF-16 at byte 4"
+            ]
+        )
+    }
+
+    #[test]
+    fn decode_lossy_should_replace_invalid_sequences() {
+        // ARRANGE
+        let utf8 = b"{ \"a\": \"\xFF\" }".to_vec();
+        let utf16 = [0xFF, 0xFE, b'{', 0, 0x00, 0xD8, b'}', 0, 0].to_vec();
+
+        // ACT
+        let result = [decode_lossy(utf8), decode_lossy(utf16)];
+
+        // ASSERT
+        assert_eq!(result, ["{ \"a\": \"\u{FFFD}\" }", "{\u{FFFD}}\u{FFFD}"])
+    }
+}
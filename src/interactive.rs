@@ -0,0 +1,330 @@
+use json2env::{redact::Redaction, EnvVar, Error};
+
+/// Let the user pick the variables to write in a terminal UI drawn on STDERR, renaming them and
+/// editing their values. Edited values become strings, the redacted ones are not shown.
+///
+/// Fails if the selection is cancelled, or if STDERR is not a terminal.
+#[cfg(feature = "interactive")]
+pub fn select(vars: Vec<EnvVar>, redaction: Option<Redaction>) -> Result<Vec<EnvVar>, Error> {
+    use std::io::IsTerminal;
+
+    if !std::io::stderr().is_terminal() {
+        let message = "`--interactive` requires STDERR to be a terminal";
+        return Err(Error::InvalidOptions(message.to_owned()));
+    }
+
+    let mut selection = tui::Selection::new(vars, redaction);
+    let terminal = tui::Terminal::open()?;
+    let selected = selection.run(terminal)?;
+
+    match selected {
+        true => Ok(selection.into_selected()),
+        false => {
+            let cancelled = std::io::ErrorKind::Interrupted;
+            Err(std::io::Error::new(cancelled, "The selection was cancelled").into())
+        }
+    }
+}
+
+#[cfg(not(feature = "interactive"))]
+pub fn select(vars: Vec<EnvVar>, redaction: Option<Redaction>) -> Result<Vec<EnvVar>, Error> {
+    let _ = (vars, redaction);
+    let message = "`--interactive` requires the `interactive` feature";
+    Err(Error::InvalidOptions(message.to_owned()))
+}
+
+#[cfg(feature = "interactive")]
+mod tui {
+    use std::io::{Stderr, Write};
+
+    use crossterm::{
+        cursor::{Hide, MoveTo, Show},
+        event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+        queue,
+        style::{Attribute, Print, SetAttribute},
+        terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+    };
+    use json2env::{
+        redact::{Redaction, REDACTED},
+        EnvVar, Error,
+    };
+
+    const HELP: &str = "Space select  a all  n rename  v edit value  Enter write  Esc cancel";
+
+    /// Raw mode and alternate screen of STDERR, restored when dropped
+    pub struct Terminal(Stderr);
+
+    impl Terminal {
+        pub fn open() -> Result<Self, Error> {
+            terminal::enable_raw_mode()?;
+
+            let mut stderr = std::io::stderr();
+            if let Err(error) = crossterm::execute!(stderr, EnterAlternateScreen, Hide) {
+                let _ = terminal::disable_raw_mode();
+                return Err(error.into());
+            }
+
+            Ok(Self(stderr))
+        }
+    }
+
+    impl Drop for Terminal {
+        fn drop(&mut self) {
+            let _ = crossterm::execute!(self.0, Show, LeaveAlternateScreen);
+            let _ = terminal::disable_raw_mode();
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Field {
+        Name,
+        Value,
+    }
+
+    struct Entry {
+        var: EnvVar,
+        selected: bool,
+    }
+
+    pub struct Selection {
+        entries: Vec<Entry>,
+        cursor: usize,
+        offset: usize,
+        // field being edited with its new text
+        editing: Option<(Field, String)>,
+        redaction: Option<Redaction>,
+    }
+
+    impl Selection {
+        pub fn new(vars: Vec<EnvVar>, redaction: Option<Redaction>) -> Self {
+            Self {
+                entries: vars
+                    .into_iter()
+                    .map(|var| Entry {
+                        var,
+                        selected: false,
+                    })
+                    .collect(),
+                cursor: 0,
+                offset: 0,
+                editing: None,
+                redaction,
+            }
+        }
+
+        /// Key and value of the variable as they are shown, the value hidden if it is redacted
+        pub(super) fn shown(&self, var: &EnvVar) -> (String, String) {
+            let (key, value) = <(String, String)>::from(var);
+            match self
+                .redaction
+                .as_ref()
+                .is_some_and(|redaction| redaction.matches(&key))
+            {
+                true => (key, REDACTED.to_owned()),
+                false => (key, value),
+            }
+        }
+
+        pub fn into_selected(self) -> Vec<EnvVar> {
+            self.entries
+                .into_iter()
+                .filter(|entry| entry.selected)
+                .map(|entry| entry.var)
+                .collect()
+        }
+
+        /// Handle the keys until the selection is confirmed, `true`, or cancelled, `false`
+        pub fn run(&mut self, mut terminal: Terminal) -> Result<bool, Error> {
+            loop {
+                self.draw(&mut terminal.0)?;
+
+                let Event::Key(key) = event::read()? else {
+                    continue;
+                };
+                if key.kind == KeyEventKind::Release {
+                    continue;
+                }
+
+                if let Some(done) = self.handle(key) {
+                    return Ok(done);
+                }
+            }
+        }
+
+        fn handle(&mut self, key: KeyEvent) -> Option<bool> {
+            if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+                return Some(false);
+            }
+
+            if let Some((field, mut text)) = self.editing.take() {
+                match key.code {
+                    KeyCode::Enter => self.apply(field, text),
+                    KeyCode::Esc => {}
+                    KeyCode::Backspace => {
+                        text.pop();
+                        self.editing = Some((field, text));
+                    }
+                    KeyCode::Char(char) => {
+                        text.push(char);
+                        self.editing = Some((field, text));
+                    }
+                    _ => self.editing = Some((field, text)),
+                }
+
+                return None;
+            }
+
+            let last = self.entries.len().saturating_sub(1);
+            match key.code {
+                KeyCode::Enter => return Some(true),
+                KeyCode::Esc | KeyCode::Char('q') => return Some(false),
+                KeyCode::Up | KeyCode::Char('k') => self.cursor = self.cursor.saturating_sub(1),
+                KeyCode::Down | KeyCode::Char('j') => self.cursor = (self.cursor + 1).min(last),
+                KeyCode::Home => self.cursor = 0,
+                KeyCode::End => self.cursor = last,
+                KeyCode::Char(' ') => {
+                    if let Some(entry) = self.entries.get_mut(self.cursor) {
+                        entry.selected = !entry.selected;
+                    }
+                }
+                KeyCode::Char('a') => {
+                    let selected = !self.entries.iter().all(|entry| entry.selected);
+                    for entry in self.entries.iter_mut() {
+                        entry.selected = selected;
+                    }
+                }
+                KeyCode::Char('n') => self.edit(Field::Name),
+                KeyCode::Char('v') => self.edit(Field::Value),
+                _ => {}
+            }
+
+            None
+        }
+
+        fn edit(&mut self, field: Field) {
+            let Some(entry) = self.entries.get(self.cursor) else {
+                return;
+            };
+
+            // redacted values are typed again instead of being revealed
+            let (key, value) = self.shown(&entry.var);
+            self.editing = Some(match field {
+                Field::Name => (field, key),
+                Field::Value if value == REDACTED => (field, String::new()),
+                Field::Value => (field, value),
+            });
+        }
+
+        /// Replace the field of the variable under the cursor, edited variables are selected
+        fn apply(&mut self, field: Field, text: String) {
+            let Some(entry) = self.entries.get_mut(self.cursor) else {
+                return;
+            };
+
+            // an empty name would not be a variable
+            if field == Field::Name && text.is_empty() {
+                return;
+            }
+
+            entry.var = match field {
+                Field::Name => entry.var.clone().with_key(text),
+                Field::Value => entry.var.clone().with_value(text),
+            };
+            entry.selected = true;
+        }
+
+        fn draw(&mut self, out: &mut Stderr) -> Result<(), Error> {
+            let (width, height) = terminal::size()?;
+            let (width, rows) = (usize::from(width), usize::from(height).saturating_sub(2));
+
+            // keep the cursor in view
+            if self.cursor < self.offset {
+                self.offset = self.cursor;
+            } else if rows > 0 && self.cursor >= self.offset + rows {
+                self.offset = self.cursor + 1 - rows;
+            }
+
+            // values spanning lines are shown on one
+            let line = |text: String| {
+                text.replace(['\r', '\n'], " ")
+                    .chars()
+                    .take(width)
+                    .collect::<String>()
+            };
+
+            queue!(out, MoveTo(0, 0), Clear(ClearType::All))?;
+            let selected = self.entries.iter().filter(|entry| entry.selected).count();
+            let title = format!("{selected} of {} selected  {HELP}", self.entries.len());
+            queue!(out, Print(line(title)))?;
+
+            for (row, entry) in self.entries.iter().enumerate().skip(self.offset).take(rows) {
+                let (key, value) = self.shown(&entry.var);
+                let check = if entry.selected { 'x' } else { ' ' };
+                let text = line(format!("[{check}] {key}={value}"));
+
+                // the row is 1-based, below the title
+                let y = u16::try_from(row - self.offset + 1).unwrap_or(u16::MAX);
+                queue!(out, MoveTo(0, y))?;
+                match row == self.cursor {
+                    true => queue!(
+                        out,
+                        SetAttribute(Attribute::Reverse),
+                        Print(text),
+                        SetAttribute(Attribute::Reset)
+                    )?,
+                    false => queue!(out, Print(text))?,
+                }
+            }
+
+            if let Some((field, ref text)) = self.editing {
+                let label = match field {
+                    Field::Name => "Name",
+                    Field::Value => "Value",
+                };
+                queue!(
+                    out,
+                    MoveTo(0, height.saturating_sub(1)),
+                    Print(line(format!("{label}: {text}_")))
+                )?;
+            }
+
+            out.flush()?;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(all(test, feature = "interactive"))]
+mod tests {
+    use json2env::{
+        redact::{Redaction, REDACTED},
+        EnvVar,
+    };
+
+    use super::tui::Selection;
+
+    #[test]
+    fn selection_should_hide_the_redacted_values() {
+        // ARRANGE
+        let vars = vec![
+            EnvVar::new("DB_PASSWORD", "hunter2"),
+            EnvVar::new("HOST", "db"),
+        ];
+        let selection = Selection::new(vars.clone(), Some(Redaction::new(["*PASSWORD"])));
+
+        // ACT
+        let result = vars
+            .iter()
+            .map(|var| selection.shown(var))
+            .collect::<Vec<_>>();
+
+        // ASSERT
+        assert_eq!(
+            result,
+            [
+                ("DB_PASSWORD".to_owned(), REDACTED.to_owned()),
+                ("HOST".to_owned(), "db".to_owned())
+            ]
+        )
+    }
+}
@@ -1,12 +1,140 @@
-use std::fmt::Display;
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::{Display, Write},
+    hash::{Hash, Hasher},
+    str::FromStr,
+    sync::Arc,
+};
 
 use serde_json::Value;
+use warning::Warning;
+
+pub use error::Error;
+
+pub mod annotation;
+pub mod apply;
+pub mod cast;
+pub mod de;
+pub mod diff;
+pub mod dotenv;
+mod error;
+#[cfg(feature = "fetch")]
+pub mod fetch;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "grouped")]
+pub mod group;
+pub mod input;
+pub mod limit;
+pub mod lint;
+#[cfg(feature = "lossless")]
+pub mod lossless;
+pub mod map;
+pub mod merge;
+#[cfg(feature = "pretty")]
+pub mod pretty;
+pub mod redact;
+pub mod render;
+pub mod secret;
+pub mod ser;
+pub mod stream;
+pub mod substitute;
+pub mod target;
+pub mod timestamp;
+pub mod types;
+pub mod unflatten;
+pub mod warning;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+/// Order in which the keys of objects are visited, and so the order of the variables
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeyOrder {
+    /// Sorted by key, whatever the map type of serde_json is
+    #[default]
+    Sorted,
+    /// Order of the keys in the document, which is kept by serde_json's `preserve_order`
+    #[cfg(feature = "preserve_order")]
+    Document,
+}
+
+/// Representation of empty objects, and of empty arrays when they are not joined
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmptyValues {
+    /// No variable, reported by [`Warning::EmptyValue`]. Joined empty arrays are empty strings.
+    #[default]
+    Skip,
+    /// Empty string variable
+    EmptyString,
+    /// `{}` or `[]` string variable, also for joined arrays
+    Literal,
+}
+
+/// Nested values split into variables of their own, the others are kept as JSON strings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flatten {
+    /// Objects are flattened, arrays are joined or kept as JSON strings if they hold nested values
+    Objects,
+    /// Arrays are enumerated, nested objects are kept as JSON strings
+    Arrays,
+    /// Objects are flattened and arrays are enumerated
+    All,
+}
+
+/// Template of the keys of array elements, like `{key}[{i}]` or `{key}_{i}_`.
+///
+/// The template starts with `{key}`, the key of the array, and contains `{i}` once, the index of
+/// the element. It replaces the key separator and the brackets of the index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexFormat {
+    before: String,
+    after: String,
+}
+
+impl FromStr for IndexFormat {
+    type Err = Error;
+
+    fn from_str(template: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            let message =
+                format!("`{template}` must start with `{{key}}` and contain `{{i}}` once");
+            Error::InvalidOptions(message)
+        };
+
+        let suffix = template.strip_prefix("{key}").ok_or_else(invalid)?;
+        let (before, after) = suffix.split_once("{i}").ok_or_else(invalid)?;
+        if after.contains("{i}") {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            before: before.to_owned(),
+            after: after.to_owned(),
+        })
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct ParseOptions {
     key_separator: String,
     array_separator: String,
     enumerate_array: bool,
+    max_depth: Option<usize>,
+    key_order: KeyOrder,
+    empty_values: EmptyValues,
+    index_brackets: bool,
+    index_start: usize,
+    index_width: usize,
+    index_format: Option<IndexFormat>,
+    array_length: Option<String>,
+    /// Separators of the arrays at the given JSON pointers, overriding the array separator
+    array_separators: HashMap<String, String>,
+    key_by: Option<String>,
+    flatten: Option<Flatten>,
+    /// Separators after the first levels of the keys, in order, deeper levels use the key separator
+    key_separators: Vec<String>,
 }
 
 impl ParseOptions {
@@ -15,67 +143,331 @@ impl ParseOptions {
             key_separator,
             array_separator,
             enumerate_array,
+            max_depth: None,
+            key_order: KeyOrder::default(),
+            empty_values: EmptyValues::default(),
+            index_brackets: false,
+            index_start: 0,
+            index_width: 0,
+            index_format: None,
+            array_length: None,
+            array_separators: HashMap::new(),
+            key_by: None,
+            flatten: None,
+            key_separators: Vec::new(),
+        }
+    }
+
+    pub fn builder() -> ParseOptionsBuilder {
+        ParseOptionsBuilder::default()
+    }
+
+    /// Separator joining the elements of the array at `pointer`
+    pub(crate) fn array_separator_at(&self, pointer: &str) -> &str {
+        self.array_separators
+            .get(pointer)
+            .unwrap_or(&self.array_separator)
+    }
+
+    /// Names of the elements of an array of objects, from the value of their key field.
+    ///
+    /// `None` if no field is configured, or if not every element has a distinct string or number in
+    /// it, in which case the elements are enumerated.
+    pub(crate) fn element_names(&self, array: &[Value]) -> Option<Vec<String>> {
+        let field = self.key_by.as_deref()?;
+
+        let names = array
+            .iter()
+            .map(|item| match item.get(field)? {
+                Value::String(name) => Some(name.clone()),
+                Value::Number(number) => Some(number.to_string()),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        let mut seen = HashSet::with_capacity(names.len());
+        names.iter().all(|name| seen.insert(name)).then_some(names)
+    }
+
+    /// Separator following the key segment at `level`, counted from 0
+    pub(crate) fn key_separator_at(&self, level: usize) -> &str {
+        self.key_separators
+            .get(level)
+            .unwrap_or(&self.key_separator)
+    }
+
+    /// Append the key of the element at `index` of an array to the key of the array, separated by
+    /// `separator`
+    pub(crate) fn push_index(&self, key: &mut String, index: usize, separator: &str) {
+        let (index, width) = (index + self.index_start, self.index_width);
+
+        // writing to a `String` cannot fail
+        if let Some(ref format) = self.index_format {
+            key.push_str(&format.before);
+            let _ = write!(key, "{index:0width$}");
+            key.push_str(&format.after);
+            return;
         }
+
+        if !key.is_empty() {
+            key.push_str(separator);
+        }
+
+        let _ = match self.index_brackets {
+            true => write!(key, "[{index:0width$}]"),
+            false => write!(key, "{index:0width$}"),
+        };
     }
 }
 
-#[derive(Debug, Clone)]
+/// Nested keys separated by `__`, arrays joined by `,` and not enumerated, no depth limit, sorted keys,
+/// empty values skipped
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self::new("__".to_owned(), ",".to_owned(), false)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptionsBuilder {
+    options: ParseOptions,
+}
+
+impl ParseOptionsBuilder {
+    pub fn key_separator(mut self, separator: impl Into<String>) -> Self {
+        self.options.key_separator = separator.into();
+        self
+    }
+
+    pub fn array_separator(mut self, separator: impl Into<String>) -> Self {
+        self.options.array_separator = separator.into();
+        self
+    }
+
+    pub fn enumerate_array(mut self, enumerate: bool) -> Self {
+        self.options.enumerate_array = enumerate;
+        self
+    }
+
+    /// Key the elements of arrays of objects by the value of their `field`, `SERVERS__web__HOST`
+    /// instead of `SERVERS__0__HOST`, if every element has a distinct string or number in it.
+    /// Rejected by [`ser::to_vars`] and [`stream::parse_reader`].
+    pub fn key_by(mut self, field: impl Into<String>) -> Self {
+        self.options.key_by = Some(field.into());
+        self
+    }
+
+    /// Separate the first levels of the keys with these separators, in order, and the deeper ones
+    /// with the key separator: `["."]` gives `db.host__primary`. Rejected by [`ser::to_vars`].
+    pub fn key_separator_levels(
+        mut self,
+        separators: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.options.key_separators = separators.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Choose which nested values are flattened, replacing the array enumeration. The root object is
+    /// always flattened. Rejected by [`ser::to_vars`] and [`stream::parse_reader`] unless it is
+    /// [`Flatten::All`].
+    pub fn flatten(mut self, flatten: Flatten) -> Self {
+        self.options.enumerate_array = matches!(flatten, Flatten::Arrays | Flatten::All);
+        self.options.flatten = Some(flatten);
+        self
+    }
+
+    /// Join the elements of the array at the JSON pointer, like `/hosts`, with `separator` instead
+    /// of the array separator. Rejected by [`ser::to_vars`], which does not track pointers.
+    pub fn array_separator_for(
+        mut self,
+        pointer: impl Into<String>,
+        separator: impl Into<String>,
+    ) -> Self {
+        self.options
+            .array_separators
+            .insert(pointer.into(), separator.into());
+        self
+    }
+
+    /// Maximum number of nested objects and arrays, the document itself being the first level.
+    ///
+    /// Deeper values are not visited: [`JsonParser::parse_with_warnings`] fails with
+    /// [`Error::TooDeep`], while [`JsonParser::parse`] returns the variables found before them.
+    /// Rejected by [`ser::to_vars`].
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.options.max_depth = Some(depth);
+        self
+    }
+
+    pub fn key_order(mut self, order: KeyOrder) -> Self {
+        self.options.key_order = order;
+        self
+    }
+
+    pub fn empty_values(mut self, empty: EmptyValues) -> Self {
+        self.options.empty_values = empty;
+        self
+    }
+
+    /// Enclose array indices in brackets, `KEY__[0]`, to tell them apart from numeric object keys
+    pub fn index_brackets(mut self, brackets: bool) -> Self {
+        self.options.index_brackets = brackets;
+        self
+    }
+
+    /// Number the elements of arrays from `start` in keys, source pointers still start from zero
+    pub fn index_start(mut self, start: usize) -> Self {
+        self.options.index_start = start;
+        self
+    }
+
+    /// Pad the indices of array elements with zeros to at least `width` digits, `KEY__01`, so that
+    /// sorting the keys keeps the order of the elements
+    pub fn index_width(mut self, width: usize) -> Self {
+        self.options.index_width = width;
+        self
+    }
+
+    /// Build the keys of array elements from a template instead of the key separator
+    pub fn index_format(mut self, format: IndexFormat) -> Self {
+        self.options.index_format = Some(format);
+        self
+    }
+
+    /// Also emit the number of elements of enumerated arrays, in the variable named after the array
+    /// and `name`, like `KEY__COUNT`. Rejected by [`ser::to_vars`].
+    pub fn array_length(mut self, name: impl Into<String>) -> Self {
+        self.options.array_length = Some(name.into());
+        self
+    }
+
+    pub fn build(self) -> ParseOptions {
+        self.options
+    }
+}
+
+/// Hook rewriting each variable before it is emitted, for custom renaming, filtering or value
+/// rewriting. Closures taking and returning an [`EnvVar`] implement it.
+pub trait Transform: Send + Sync {
+    /// Return the variable to emit in place of `var`, or `None` to drop it
+    fn transform(&self, var: EnvVar) -> Option<EnvVar>;
+}
+
+impl<F> Transform for F
+where
+    F: Fn(EnvVar) -> Option<EnvVar> + Send + Sync,
+{
+    fn transform(&self, var: EnvVar) -> Option<EnvVar> {
+        self(var)
+    }
+}
+
+#[derive(Clone)]
 pub struct JsonParser {
     options: ParseOptions,
+    transforms: Vec<Arc<dyn Transform>>,
+}
+
+impl std::fmt::Debug for JsonParser {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt.debug_struct("JsonParser")
+            .field("options", &self.options)
+            .field("transforms", &self.transforms.len())
+            .finish()
+    }
 }
 
 impl JsonParser {
     pub fn new(options: ParseOptions) -> Self {
-        Self { options }
+        Self {
+            options,
+            transforms: Vec::new(),
+        }
     }
 
-    pub fn parse(&mut self, json: &Value) -> Vec<EnvVar> {
-        Self::parse_value("", json, &self.options)
+    /// Add a hook run on every variable, after the ones added before it
+    pub fn with_transform(mut self, transform: impl Transform + 'static) -> Self {
+        self.transforms.push(Arc::new(transform));
+        self
     }
 
-    fn parse_value(key: &str, value: &Value, options: &ParseOptions) -> Vec<EnvVar> {
-        match value {
-            Value::Array(array) => {
-                let has_complex_values = array
-                    .iter()
-                    .any(|value| value.is_object() || value.is_array());
+    pub fn parse(&self, json: &Value) -> Vec<EnvVar> {
+        self.parse_iter(json).collect()
+    }
 
-                // complex (nested) values cannot be part of an array enumeration, skip just this array
-                if options.enumerate_array || has_complex_values {
-                    let mut values = Vec::with_capacity(array.len());
+    /// Lazily yield the variables of the document, in the same order as [`JsonParser::parse`]
+    pub fn parse_iter<'a>(&'a self, json: &'a Value) -> impl Iterator<Item = EnvVar> + 'a {
+        ParseIter::new(Cow::Borrowed(json), self)
+    }
 
-                    for (index, item) in array.iter().enumerate() {
-                        let key = Self::build_key(key, &index.to_string(), &options.key_separator);
-                        values.push(Self::parse_value(&key, item, options));
-                    }
+    /// Parse a document that is not needed anymore, moving its values instead of cloning them
+    pub fn parse_owned(&self, json: Value) -> Vec<EnvVar> {
+        ParseIter::new(Cow::Owned(json), self).collect()
+    }
 
-                    values.into_iter().flatten().collect()
-                } else {
-                    let value = array
-                        .iter()
-                        .map(|value| value.to_string().replace(['\\', '"'], ""))
-                        .collect::<Vec<_>>()
-                        .join(&options.array_separator);
+    /// Parse the document, also reporting the issues found along the way.
+    /// Fails if the document is nested deeper than [`ParseOptionsBuilder::max_depth`].
+    pub fn parse_with_warnings(&self, json: &Value) -> Result<(Vec<EnvVar>, Vec<Warning>), Error> {
+        Self::collect_with_warnings(ParseIter::new(Cow::Borrowed(json), self))
+    }
+
+    /// Owning variant of [`JsonParser::parse_with_warnings`], see [`JsonParser::parse_owned`]
+    pub fn parse_owned_with_warnings(
+        &self,
+        json: Value,
+    ) -> Result<(Vec<EnvVar>, Vec<Warning>), Error> {
+        Self::collect_with_warnings(ParseIter::new(Cow::Owned(json), self))
+    }
 
-                    let value = serde_json::Value::String(value);
-                    Self::parse_value(key, &value, options)
+    fn collect_with_warnings(mut iter: ParseIter) -> Result<(Vec<EnvVar>, Vec<Warning>), Error> {
+        let vars = iter.by_ref().collect::<Vec<_>>();
+        if let Some(error) = iter.error {
+            return Err(error);
+        }
+
+        let mut warnings = iter.warnings;
+
+        let mut seen = HashMap::with_capacity(vars.len());
+        for var in vars.iter() {
+            match seen.get(var.0.as_str()) {
+                Some(first) => warnings.push(Warning::Collision {
+                    key: var.0.clone(),
+                    sources: [first, &var.2].into_iter().flatten().cloned().collect(),
+                }),
+                None => {
+                    seen.insert(var.0.as_str(), var.2.clone());
                 }
             }
-            Value::Object(object) => {
-                let mut values = Vec::with_capacity(object.len());
+        }
 
-                for (name, value) in object.iter() {
-                    let key = Self::build_key(key, name, &options.key_separator);
-                    values.push(Self::parse_value(&key, value, options));
-                }
+        Ok((vars, warnings))
+    }
+
+    /// Join the elements of an array of scalars in a single string value
+    pub(crate) fn join_array(array: &[Value], separator: &str) -> Value {
+        let value = array
+            .iter()
+            .map(|value| value.to_string().replace(['\\', '"'], ""))
+            .collect::<Vec<_>>()
+            .join(separator);
 
-                values.into_iter().flatten().collect()
+        Value::String(value)
+    }
+
+    /// Append a segment to a JSON pointer, escaping `~` and `/` as required by RFC 6901
+    pub(crate) fn push_pointer_segment(pointer: &mut String, segment: &str) {
+        pointer.push('/');
+        for char in segment.chars() {
+            match char {
+                '~' => pointer.push_str("~0"),
+                '/' => pointer.push_str("~1"),
+                char => pointer.push(char),
             }
-            _ => vec![EnvVar(key.trim().to_owned(), value.clone())],
         }
     }
 
-    fn build_key(prefix: &str, key: &str, separator: &str) -> String {
+    #[cfg(test)]
+    pub(crate) fn build_key(prefix: &str, key: &str, separator: &str) -> String {
         match prefix.is_empty() {
             true => key.to_string(),
             false => format!("{prefix}{separator}{key}"),
@@ -83,8 +475,368 @@ impl JsonParser {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct EnvVar(String, Value);
+/// Value still to be visited by the traversal
+struct Node<'a> {
+    /// Name of the value in its parent, `None` for the document itself
+    name: Option<Segment<'a>>,
+    /// Lengths of the key and pointer of the parent, the prefixes of the ones of the value
+    parent: (usize, usize),
+    /// Number of objects and arrays containing the value
+    depth: usize,
+    /// Borrowed values are cloned when emitted, owned ones are moved
+    value: Cow<'a, Value>,
+}
+
+enum Segment<'a> {
+    Index(usize),
+    Name(Cow<'a, str>),
+    /// Number of elements of the parent array, which is not part of the document
+    Length,
+    /// Element of an array named after its key field, see [`ParseOptionsBuilder::key_by`]
+    Keyed(usize, String),
+}
+
+/// Depth-first traversal of a document, keeping the values still to be visited on a stack
+struct ParseIter<'a> {
+    options: &'a ParseOptions,
+    transforms: &'a [Arc<dyn Transform>],
+    stack: Vec<Node<'a>>,
+    /// Key and JSON pointer of the current value, shared by all the values to spare allocations.
+    /// They always start with the ones of the parent of the next value on the stack.
+    key: String,
+    pointer: String,
+    warnings: Vec<Warning>,
+    /// Reason the traversal stopped early
+    error: Option<Error>,
+}
+
+impl<'a> ParseIter<'a> {
+    fn new(json: Cow<'a, Value>, parser: &'a JsonParser) -> Self {
+        Self {
+            options: &parser.options,
+            transforms: &parser.transforms,
+            stack: vec![Node {
+                name: None,
+                parent: (0, 0),
+                depth: 0,
+                value: json,
+            }],
+            key: String::new(),
+            pointer: String::new(),
+            warnings: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Point the key and the pointer to the value at `depth`, replacing the ones of the previous
+    /// value
+    fn enter(&mut self, (key, pointer): (usize, usize), name: Option<Segment>, depth: usize) {
+        self.key.truncate(key);
+        self.pointer.truncate(pointer);
+
+        // the parent key has a segment per level above the value, the root has none
+        let separator = self.options.key_separator_at(depth.saturating_sub(2));
+        if !self.key.is_empty() && !matches!(name, None | Some(Segment::Index(_))) {
+            self.key.push_str(separator);
+        }
+
+        match name {
+            None => {}
+            Some(Segment::Index(index)) => {
+                self.options.push_index(&mut self.key, index, separator);
+                // writing to a `String` cannot fail
+                let _ = write!(self.pointer, "/{index}");
+            }
+            Some(Segment::Name(name)) => {
+                self.key.push_str(&name);
+                JsonParser::push_pointer_segment(&mut self.pointer, &name);
+            }
+            Some(Segment::Keyed(index, name)) => {
+                self.key.push_str(&name);
+                let _ = write!(self.pointer, "/{index}");
+            }
+            Some(Segment::Length) => {
+                let name = self.options.array_length.as_deref().unwrap_or_default();
+                self.key.push_str(name);
+            }
+        }
+    }
+
+    /// Variable holding the value, if not dropped by the transforms
+    fn leaf(&mut self, value: Value) -> Option<EnvVar> {
+        let trimmed = self.key.trim();
+        if trimmed.len() != self.key.len() {
+            self.warnings.push(Warning::TrimmedKey {
+                key: self.key.clone(),
+            });
+        }
+
+        let var = EnvVar::new(trimmed, value).with_source_pointer(self.pointer.as_str());
+        self.transforms
+            .iter()
+            .try_fold(var, |var, transform| transform.transform(var))
+    }
+
+    /// Schedule the length of an enumerated array after its elements, if requested
+    fn push_length(&mut self, depth: usize, len: usize) {
+        if self.options.array_length.is_some() {
+            self.stack.push(Node {
+                name: Some(Segment::Length),
+                parent: (self.key.len(), self.pointer.len()),
+                depth: depth + 1,
+                value: Cow::Owned(Value::from(len)),
+            });
+        }
+    }
+
+    /// Schedule the elements of an array or object, in reverse to visit them in order
+    fn push_children(&mut self, depth: usize, value: Cow<'a, Value>) {
+        // maps are only unsorted if `preserve_order` is enabled, possibly by another crate
+        let sort = match &*value {
+            Value::Object(object) => {
+                self.options.key_order == KeyOrder::Sorted && !object.keys().is_sorted()
+            }
+            _ => false,
+        };
+
+        let names = match &*value {
+            Value::Array(array) => self.options.element_names(array),
+            _ => None,
+        };
+        let segment = |index| match names {
+            Some(ref names) => Segment::Keyed(index, names[index].clone()),
+            None => Segment::Index(index),
+        };
+
+        let parent = (self.key.len(), self.pointer.len());
+        let node = |name, value| Node {
+            name: Some(name),
+            parent,
+            depth: depth + 1,
+            value,
+        };
+
+        match value {
+            Cow::Borrowed(Value::Array(array)) => self.stack.extend(
+                array
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .map(|(index, item)| node(segment(index), Cow::Borrowed(item))),
+            ),
+            Cow::Borrowed(Value::Object(object)) if sort => {
+                let mut entries = object.iter().collect::<Vec<_>>();
+                entries.sort_by_key(|(name, _)| *name);
+
+                self.stack
+                    .extend(entries.into_iter().rev().map(|(name, item)| {
+                        node(Segment::Name(Cow::Borrowed(name)), Cow::Borrowed(item))
+                    }))
+            }
+            Cow::Borrowed(Value::Object(object)) => {
+                self.stack.extend(object.iter().rev().map(|(name, item)| {
+                    node(Segment::Name(Cow::Borrowed(name)), Cow::Borrowed(item))
+                }))
+            }
+            Cow::Owned(Value::Array(array)) => self.stack.extend(
+                array
+                    .into_iter()
+                    .enumerate()
+                    .rev()
+                    .map(|(index, item)| node(segment(index), Cow::Owned(item))),
+            ),
+            Cow::Owned(Value::Object(object)) if sort => {
+                let mut entries = object.into_iter().collect::<Vec<_>>();
+                entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+                self.stack.extend(
+                    entries.into_iter().rev().map(|(name, item)| {
+                        node(Segment::Name(Cow::Owned(name)), Cow::Owned(item))
+                    }),
+                )
+            }
+            Cow::Owned(Value::Object(object)) => self.stack.extend(
+                object
+                    .into_iter()
+                    .rev()
+                    .map(|(name, item)| node(Segment::Name(Cow::Owned(name)), Cow::Owned(item))),
+            ),
+            _ => unreachable!("only arrays and objects have children"),
+        }
+    }
+
+    /// Variable representing an empty object or array, if any
+    fn empty(&mut self, literal: &str) -> Option<EnvVar> {
+        // an empty document has no key to hold it, and is not worth a warning
+        if self.key.is_empty() {
+            return None;
+        }
+
+        match self.options.empty_values {
+            EmptyValues::Skip => {
+                let key = self.key.clone();
+                self.warnings.push(Warning::EmptyValue { key });
+                None
+            }
+            EmptyValues::EmptyString => self.leaf(Value::from("")),
+            EmptyValues::Literal => self.leaf(Value::from(literal)),
+        }
+    }
+}
+
+impl<'a> Iterator for ParseIter<'a> {
+    type Item = EnvVar;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let options = self.options;
+
+        while let Some(Node {
+            name,
+            parent,
+            depth,
+            value,
+        }) = self.stack.pop()
+        {
+            self.enter(parent, name, depth);
+
+            let nested = value.is_object() || value.is_array();
+            if let Some(limit) = options.max_depth.filter(|&limit| nested && depth >= limit) {
+                self.stack.clear();
+                self.error = Some(Error::TooDeep {
+                    pointer: self.pointer.clone(),
+                    limit,
+                });
+                return None;
+            }
+
+            let empty = match &*value {
+                Value::Object(object) if object.is_empty() => Some("{}"),
+                Value::Array(array)
+                    if array.is_empty()
+                        && (options.enumerate_array
+                            || options.empty_values == EmptyValues::Literal) =>
+                {
+                    Some("[]")
+                }
+                _ => None,
+            };
+
+            if let Some(literal) = empty {
+                if value.is_array() && options.enumerate_array {
+                    self.push_length(depth, 0);
+                }
+
+                match self.empty(literal) {
+                    Some(var) => return Some(var),
+                    None => continue,
+                }
+            }
+
+            match &*value {
+                Value::Array(array) => {
+                    let has_complex_values = array
+                        .iter()
+                        .any(|value| value.is_object() || value.is_array());
+
+                    if has_complex_values && options.flatten == Some(Flatten::Objects) {
+                        if let Some(var) = self.leaf(Value::String(value.to_string())) {
+                            return Some(var);
+                        }
+                        continue;
+                    }
+
+                    // a root array holds documents, each prefixed by its index on purpose
+                    if has_complex_values && !options.enumerate_array && depth > 0 {
+                        let key = self.key.clone();
+                        self.warnings.push(Warning::ComplexArray { key });
+                    }
+
+                    // complex (nested) values cannot be part of an array enumeration, skip just this array
+                    if options.enumerate_array || has_complex_values {
+                        self.push_length(depth, array.len());
+                        self.push_children(depth, value);
+                    } else {
+                        let separator = options.array_separator_at(&self.pointer);
+                        let value = JsonParser::join_array(array, separator);
+                        if let Some(var) = self.leaf(value) {
+                            return Some(var);
+                        }
+                    }
+                }
+                Value::Object(_) if options.flatten == Some(Flatten::Arrays) && depth > 0 => {
+                    if let Some(var) = self.leaf(Value::String(value.to_string())) {
+                        return Some(var);
+                    }
+                }
+                Value::Object(_) => self.push_children(depth, value),
+                _ => {
+                    if let Some(var) = self.leaf(value.into_owned()) {
+                        return Some(var);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Variable generated from a value, two variables are equal if they have the same key and value
+#[derive(Debug, Clone)]
+pub struct EnvVar(String, Value, Option<String>);
+
+impl EnvVar {
+    pub fn new(key: impl Into<String>, value: impl Into<Value>) -> Self {
+        Self(key.into(), value.into(), None)
+    }
+
+    /// Record the JSON pointer of the value in the document the variable comes from
+    pub fn with_source_pointer(mut self, pointer: impl Into<String>) -> Self {
+        self.2 = Some(pointer.into());
+        self
+    }
+
+    pub fn key(&self) -> &str {
+        &self.0
+    }
+
+    /// Replace the key, keeping the value and the source pointer
+    pub fn with_key(mut self, key: impl Into<String>) -> Self {
+        self.0 = key.into();
+        self
+    }
+
+    pub fn value(&self) -> &Value {
+        &self.1
+    }
+
+    /// Replace the value, keeping the key and the source pointer
+    pub fn with_value(mut self, value: impl Into<Value>) -> Self {
+        self.1 = value.into();
+        self
+    }
+
+    /// JSON pointer of the value in the parsed document, if the variable comes from one
+    pub fn source_pointer(&self) -> Option<&str> {
+        self.2.as_deref()
+    }
+
+    pub fn into_parts(self) -> (String, Value) {
+        (self.0, self.1)
+    }
+
+    /// Render the variable as a `KEY=value` line.
+    /// Unlike `Display`, which writes nothing for objects and arrays, this fails for them.
+    pub fn render(&self) -> Result<String, Error> {
+        match self.1 {
+            Value::Array(_) | Value::Object(_) => Err(Error::Render {
+                key: self.0.clone(),
+                reason: "nested values are not supported".to_owned(),
+            }),
+            _ => Ok(self.to_string()),
+        }
+    }
+}
 
 impl Display for EnvVar {
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -103,11 +855,143 @@ impl Display for EnvVar {
     }
 }
 
+/// Key and unquoted value of the variable as they would be read back from a `.env` file
+impl From<&EnvVar> for (String, String) {
+    fn from(var: &EnvVar) -> Self {
+        let value = match var.1 {
+            Value::String(ref string) => string.clone(),
+            ref value => value.to_string(),
+        };
+
+        (var.0.clone(), value)
+    }
+}
+
+impl PartialEq for EnvVar {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0 && self.1 == other.1
+    }
+}
+
+impl Eq for EnvVar {}
+
+/// Variables are sorted by key, then by value: `null`, booleans, numbers, strings, arrays and objects
+impl Ord for EnvVar {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0
+            .cmp(&other.0)
+            .then_with(|| compare_values(&self.1, &other.1))
+    }
+}
+
+impl PartialOrd for EnvVar {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Hash for EnvVar {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+        hash_value(&self.1, state);
+    }
+}
+
+/// Total order of JSON values consistent with their equality
+fn compare_values(a: &Value, b: &Value) -> Ordering {
+    let rank = |value: &Value| match value {
+        Value::Null => 0,
+        Value::Bool(_) => 1,
+        Value::Number(_) => 2,
+        Value::String(_) => 3,
+        Value::Array(_) => 4,
+        Value::Object(_) => 5,
+    };
+
+    match (a, b) {
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        // integers and floats of the same magnitude are different values, their text tells them apart
+        (Value::Number(x), Value::Number(y)) if x != y => x
+            .as_f64()
+            .partial_cmp(&y.as_f64())
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| x.to_string().cmp(&y.to_string())),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Array(a), Value::Array(b)) => a
+            .iter()
+            .zip(b)
+            .map(|(a, b)| compare_values(a, b))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| a.len().cmp(&b.len())),
+        (Value::Object(a), Value::Object(b)) => sorted_entries(a)
+            .into_iter()
+            .zip(sorted_entries(b))
+            .map(|((a_key, a), (b_key, b))| a_key.cmp(b_key).then_with(|| compare_values(a, b)))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| a.len().cmp(&b.len())),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+/// Entries of the object sorted by key, objects are equal whatever the order of their entries
+fn sorted_entries(object: &serde_json::Map<String, Value>) -> Vec<(&String, &Value)> {
+    let mut entries = object.iter().collect::<Vec<_>>();
+    entries.sort_by_key(|(key, _)| *key);
+    entries
+}
+
+fn hash_value<H: Hasher>(value: &Value, state: &mut H) {
+    std::mem::discriminant(value).hash(state);
+
+    match value {
+        Value::Null => {}
+        Value::Bool(bool) => bool.hash(state),
+        Value::Number(number) => number.hash(state),
+        Value::String(string) => string.hash(state),
+        Value::Array(array) => {
+            array.len().hash(state);
+            array.iter().for_each(|value| hash_value(value, state));
+        }
+        Value::Object(object) => {
+            object.len().hash(state);
+            for (key, value) in sorted_entries(object) {
+                key.hash(state);
+                hash_value(value, state);
+            }
+        }
+    }
+}
+
+/// Collect the variables as unquoted values, later definitions of a key override the earlier ones
+impl FromIterator<EnvVar> for BTreeMap<String, String> {
+    fn from_iter<T: IntoIterator<Item = EnvVar>>(iter: T) -> Self {
+        iter.into_iter()
+            .map(|var| <(String, String)>::from(&var))
+            .collect()
+    }
+}
+
+/// Collect the variables as unquoted values, later definitions of a key override the earlier ones
+impl FromIterator<EnvVar> for HashMap<String, String> {
+    fn from_iter<T: IntoIterator<Item = EnvVar>>(iter: T) -> Self {
+        iter.into_iter()
+            .map(|var| <(String, String)>::from(&var))
+            .collect()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use serde_json::{json, Value};
+    use std::collections::{BTreeMap, HashMap, HashSet};
+
+    use serde_json::{json, Map, Value};
 
-    use crate::{EnvVar, JsonParser, ParseOptions};
+    #[cfg(feature = "preserve_order")]
+    use crate::KeyOrder;
+    use crate::{
+        warning::Warning, EmptyValues, EnvVar, Error, Flatten, IndexFormat, JsonParser,
+        ParseOptions,
+    };
 
     const KEY: &str = r#""key""#;
 
@@ -142,7 +1026,7 @@ mod tests {
     #[test]
     fn bool_env_var_should_be_formatted_correctly() {
         // ARRANGE
-        let input = EnvVar(KEY.to_owned(), json!(true));
+        let input = EnvVar::new(KEY.to_owned(), json!(true));
 
         // ACT
         let result = input.to_string();
@@ -154,7 +1038,7 @@ mod tests {
     #[test]
     fn numeric_env_var_should_be_formatted_correctly() {
         // ARRANGE
-        let input = EnvVar(KEY.to_owned(), json!(1.0));
+        let input = EnvVar::new(KEY.to_owned(), json!(1.0));
 
         // ACT
         let result = input.to_string();
@@ -166,7 +1050,7 @@ mod tests {
     #[test]
     fn string_env_var_should_be_formatted_correctly() {
         // ARRANGE
-        let input = EnvVar(KEY.to_owned(), json!("hello"));
+        let input = EnvVar::new(KEY.to_owned(), json!("hello"));
 
         // ACT
         let result = input.to_string();
@@ -178,7 +1062,7 @@ mod tests {
     #[test]
     fn array_env_var_should_be_formatted_correctly() {
         // ARRANGE
-        let input = EnvVar(KEY.to_owned(), json!([1, 2]));
+        let input = EnvVar::new(KEY.to_owned(), json!([1, 2]));
 
         // ACT
         let result = input.to_string();
@@ -190,7 +1074,7 @@ mod tests {
     #[test]
     fn object_env_var_should_be_formatted_correctly() {
         // ARRANGE
-        let input = EnvVar(KEY.to_owned(), json!({ "key": "value" }));
+        let input = EnvVar::new(KEY.to_owned(), json!({ "key": "value" }));
 
         // ACT
         let result = input.to_string();
@@ -200,21 +1084,57 @@ mod tests {
     }
 
     #[test]
-    fn parse_array_not_enumerated() {
+    fn env_var_accessors_should_return_parts() {
         // ARRANGE
-        let json = json!({ "array": [1, 2, 3] });
-        let options = ParseOptions::new("__".to_string(), ",".to_string(), false);
-        let mut parser = JsonParser::new(options);
+        let input = EnvVar::new(KEY, 42);
 
         // ACT
-        let environ = parser.parse(&json);
+        let (key, value) = (input.key().to_owned(), input.value().clone());
 
         // ASSERT
-        assert_eq!(
-            *environ,
-            vec![EnvVar(
-                "array".to_string(),
-                Value::String("1,2,3".to_string())
+        assert_eq!((key, value), input.into_parts())
+    }
+
+    #[test]
+    fn render_should_fail_for_nested_values() {
+        // ARRANGE
+        let input = EnvVar::new(KEY, json!([1, 2]));
+
+        // ACT
+        let result = input.render();
+
+        // ASSERT
+        assert!(matches!(result, Err(Error::Render { .. })))
+    }
+
+    #[test]
+    fn env_var_should_convert_to_unquoted_pair() {
+        // ARRANGE
+        let input = EnvVar::new(KEY.to_owned(), json!("hello"));
+
+        // ACT
+        let (key, value) = <(String, String)>::from(&input);
+
+        // ASSERT
+        assert_eq!((key.as_str(), value.as_str()), (KEY, "hello"))
+    }
+
+    #[test]
+    fn parse_array_not_enumerated() {
+        // ARRANGE
+        let json = json!({ "array": [1, 2, 3] });
+        let options = ParseOptions::new("__".to_string(), ",".to_string(), false);
+        let parser = JsonParser::new(options);
+
+        // ACT
+        let environ = parser.parse(&json);
+
+        // ASSERT
+        assert_eq!(
+            *environ,
+            vec![EnvVar::new(
+                "array".to_string(),
+                Value::String("1,2,3".to_string())
             )]
         )
     }
@@ -224,7 +1144,7 @@ mod tests {
         // ARRANGE
         let json = json!({ "array": [1, 2, 3] });
         let options = ParseOptions::new("__".to_string(), ",".to_string(), true);
-        let mut parser = JsonParser::new(options);
+        let parser = JsonParser::new(options);
 
         // ACT
         let environ = parser.parse(&json);
@@ -233,10 +1153,650 @@ mod tests {
         assert_eq!(
             *environ,
             vec![
-                EnvVar("array__0".to_string(), Value::Number(1.into())),
-                EnvVar("array__1".to_string(), Value::Number(2.into())),
-                EnvVar("array__2".to_string(), Value::Number(3.into()))
+                EnvVar::new("array__0".to_string(), Value::Number(1.into())),
+                EnvVar::new("array__1".to_string(), Value::Number(2.into())),
+                EnvVar::new("array__2".to_string(), Value::Number(3.into()))
             ]
         )
     }
+
+    #[test]
+    fn parse_with_builder_defaults() {
+        // ARRANGE
+        let json = json!({ "nested": { "array": [1, 2] } });
+        let options = ParseOptions::builder().build();
+        let parser = JsonParser::new(options);
+
+        // ACT
+        let environ = parser.parse(&json);
+
+        // ASSERT
+        assert_eq!(*environ, vec![EnvVar::new("nested__array", "1,2")])
+    }
+
+    #[test]
+    fn parse_with_builder_overrides() {
+        // ARRANGE
+        let json = json!({ "nested": { "array": [1, 2] } });
+        let options = ParseOptions::builder()
+            .key_separator(".")
+            .enumerate_array(true)
+            .build();
+        let parser = JsonParser::new(options);
+
+        // ACT
+        let environ = parser.parse(&json);
+
+        // ASSERT
+        assert_eq!(
+            *environ,
+            vec![
+                EnvVar::new("nested.array.0", 1),
+                EnvVar::new("nested.array.1", 2)
+            ]
+        )
+    }
+
+    #[test]
+    fn parser_should_be_shared_across_threads() {
+        // ARRANGE
+        let json = json!({ "key": "value" });
+        let parser = JsonParser::new(ParseOptions::default());
+
+        // ACT
+        let results = std::thread::scope(|scope| {
+            let handles = (0..2)
+                .map(|_| scope.spawn(|| parser.parse(&json)))
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        // ASSERT
+        assert_eq!(results[0], results[1])
+    }
+
+    #[test]
+    fn parse_should_handle_deeply_nested_documents() {
+        // ARRANGE
+        // built by hand, `json!` would clone and drop each level recursively
+        let json = (0..10_000).fold(json!(1), |value, _| {
+            Value::Object(Map::from_iter([("a".to_owned(), value)]))
+        });
+        let parser = JsonParser::new(ParseOptions::builder().key_separator("").build());
+
+        // ACT
+        // moving the values out also spares the recursive drop of the document
+        let result = parser.parse_owned(json);
+
+        // ASSERT
+        assert_eq!(result, vec![EnvVar::new("a".repeat(10_000), 1)])
+    }
+
+    #[test]
+    #[cfg(feature = "preserve_order")]
+    fn parse_should_follow_the_key_order() {
+        // ARRANGE
+        let json = serde_json::from_str::<Value>(r#"{ "b": 1, "a": { "d": 2, "c": 3 } }"#).unwrap();
+        let keys = |order| {
+            let parser = JsonParser::new(ParseOptions::builder().key_order(order).build());
+            let vars = parser.parse(&json);
+
+            vars.iter()
+                .map(|var| var.key().to_owned())
+                .collect::<Vec<_>>()
+        };
+
+        // ACT
+        let result = [keys(KeyOrder::Sorted), keys(KeyOrder::Document)];
+
+        // ASSERT
+        assert_eq!(result, [["a__c", "a__d", "b"], ["b", "a__d", "a__c"]])
+    }
+
+    #[test]
+    fn parse_should_represent_empty_values_as_configured() {
+        // ARRANGE
+        let json = json!({ "object": {}, "array": [], "list": [[]] });
+        let parse = |empty, enumerate| {
+            let options = ParseOptions::builder()
+                .empty_values(empty)
+                .enumerate_array(enumerate)
+                .build();
+
+            JsonParser::new(options).parse(&json)
+        };
+
+        // ACT
+        let skipped = parse(EmptyValues::Skip, true);
+        let empty_strings = parse(EmptyValues::EmptyString, false);
+        let literals = parse(EmptyValues::Literal, false);
+
+        // ASSERT
+        assert_eq!(skipped, vec![]);
+        assert_eq!(
+            empty_strings,
+            vec![
+                EnvVar::new("array", ""),
+                EnvVar::new("list__0", ""),
+                EnvVar::new("object", ""),
+            ]
+        );
+        assert_eq!(
+            literals,
+            vec![
+                EnvVar::new("array", "[]"),
+                EnvVar::new("list__0", "[]"),
+                EnvVar::new("object", "{}"),
+            ]
+        )
+    }
+
+    #[test]
+    fn parse_should_enclose_array_indices_in_brackets_when_enabled() {
+        // ARRANGE
+        let json = json!({ "map": { "0": "a" }, "list": ["a", { "b": 1 }] });
+        let options = ParseOptions::builder()
+            .enumerate_array(true)
+            .index_brackets(true)
+            .build();
+
+        // ACT
+        let result = JsonParser::new(options).parse(&json);
+
+        // ASSERT
+        assert_eq!(
+            result,
+            vec![
+                EnvVar::new("list__[0]", "a"),
+                EnvVar::new("list__[1]__b", 1),
+                EnvVar::new("map__0", "a"),
+            ]
+        )
+    }
+
+    #[test]
+    fn parse_should_number_array_elements_from_the_index_start() {
+        // ARRANGE
+        let json = json!({ "list": ["a", ["b"]] });
+        let options = ParseOptions::builder()
+            .enumerate_array(true)
+            .index_start(1)
+            .build();
+
+        // ACT
+        let result = JsonParser::new(options).parse(&json);
+
+        // ASSERT
+        assert_eq!(
+            result,
+            vec![EnvVar::new("list__1", "a"), EnvVar::new("list__2__1", "b")]
+        );
+        assert_eq!(result[1].source_pointer(), Some("/list/1/0"))
+    }
+
+    #[test]
+    fn parse_should_pad_array_indices_to_the_index_width() {
+        // ARRANGE
+        let json = json!({ "list": (0..11).collect::<Vec<_>>() });
+        let options = ParseOptions::builder()
+            .enumerate_array(true)
+            .index_width(2)
+            .build();
+
+        // ACT
+        let result = JsonParser::new(options).parse(&json);
+
+        // ASSERT
+        let keys = result.iter().map(EnvVar::key).collect::<Vec<_>>();
+        assert_eq!(keys.first(), Some(&"list__00"));
+        assert_eq!(keys.last(), Some(&"list__10"));
+        assert!(keys.is_sorted())
+    }
+
+    #[test]
+    fn parse_should_build_array_keys_from_the_index_format() {
+        // ARRANGE
+        let json = json!({ "list": [{ "name": "a" }, ["b"]] });
+        let options = ParseOptions::builder()
+            .key_separator(".")
+            .enumerate_array(true)
+            .index_format("{key}[{i}]".parse().unwrap())
+            .build();
+
+        // ACT
+        let result = JsonParser::new(options).parse(&json);
+
+        // ASSERT
+        assert_eq!(
+            result,
+            vec![
+                EnvVar::new("list[0].name", "a"),
+                EnvVar::new("list[1][0]", "b")
+            ]
+        )
+    }
+
+    #[test]
+    fn index_format_should_reject_templates_without_key_prefix_or_single_index() {
+        // ARRANGE
+        let templates = ["{i}_{key}", "{key}_", "{key}_{i}_{i}"];
+
+        // ACT
+        let result = templates.map(str::parse::<IndexFormat>);
+
+        // ASSERT
+        assert!(result
+            .iter()
+            .all(|result| matches!(result, Err(Error::InvalidOptions(_)))))
+    }
+
+    #[test]
+    fn parse_should_emit_the_length_of_enumerated_arrays_when_enabled() {
+        // ARRANGE
+        let json = json!({ "empty": [], "joined": [1, 2], "nested": [[1], { "a": 1 }] });
+        let options = ParseOptions::builder().array_length("COUNT").build();
+
+        // ACT
+        let result = JsonParser::new(options).parse(&json);
+
+        // ASSERT
+        assert_eq!(
+            result,
+            vec![
+                EnvVar::new("empty", ""),
+                EnvVar::new("joined", "1,2"),
+                EnvVar::new("nested__0", "1"),
+                EnvVar::new("nested__1__a", 1),
+                EnvVar::new("nested__COUNT", 2),
+            ]
+        );
+        assert_eq!(result[4].source_pointer(), Some("/nested"))
+    }
+
+    #[test]
+    fn parse_should_join_arrays_with_their_separator_override() {
+        // ARRANGE
+        let json = json!({ "hosts": ["a", "b"], "db": { "hosts": ["c", "d"] }, "tags": [1, 2] });
+        let options = ParseOptions::builder()
+            .array_separator_for("/hosts", ";")
+            .array_separator_for("/db/hosts", " ")
+            .build();
+
+        // ACT
+        let result = JsonParser::new(options).parse(&json);
+
+        // ASSERT
+        assert_eq!(
+            result,
+            vec![
+                EnvVar::new("db__hosts", "c d"),
+                EnvVar::new("hosts", "a;b"),
+                EnvVar::new("tags", "1,2"),
+            ]
+        )
+    }
+
+    #[test]
+    fn parse_should_key_arrays_of_objects_by_field_when_enabled() {
+        // ARRANGE
+        let json = json!({
+            "servers": [{ "name": "web", "host": "a" }, { "name": "db", "host": "b" }],
+            "duplicates": [{ "name": "x" }, { "name": "x" }]
+        });
+        let options = ParseOptions::builder().key_by("name").build();
+
+        // ACT
+        let result = JsonParser::new(options).parse(&json);
+
+        // ASSERT
+        assert_eq!(
+            result,
+            vec![
+                EnvVar::new("duplicates__0__name", "x"),
+                EnvVar::new("duplicates__1__name", "x"),
+                EnvVar::new("servers__web__host", "a"),
+                EnvVar::new("servers__web__name", "web"),
+                EnvVar::new("servers__db__host", "b"),
+                EnvVar::new("servers__db__name", "db"),
+            ]
+        );
+        assert_eq!(result[2].source_pointer(), Some("/servers/0/host"))
+    }
+
+    #[test]
+    fn parse_should_separate_the_first_levels_with_their_separators() {
+        // ARRANGE
+        let json = json!({
+            "app": { "db": { "primary": { "host": "a" } }, "hosts": ["b", "c"] },
+            "port": 1
+        });
+        let options = ParseOptions::builder()
+            .key_separator_levels(["."])
+            .enumerate_array(true)
+            .build();
+
+        // ACT
+        let result = JsonParser::new(options).parse(&json);
+
+        // ASSERT
+        assert_eq!(
+            result,
+            vec![
+                EnvVar::new("app.db__primary__host", "a"),
+                EnvVar::new("app.hosts__0", "b"),
+                EnvVar::new("app.hosts__1", "c"),
+                EnvVar::new("port", 1),
+            ]
+        )
+    }
+
+    #[test]
+    fn parse_should_keep_the_values_not_flattened_as_json() {
+        // ARRANGE
+        let json = json!({
+            "db": { "host": "localhost" },
+            "hosts": ["a", "b"],
+            "servers": [{ "name": "web" }]
+        });
+        let parse = |flatten| {
+            let options = ParseOptions::builder().flatten(flatten).build();
+            JsonParser::new(options).parse_with_warnings(&json).unwrap()
+        };
+
+        // ACT
+        let result = [Flatten::Objects, Flatten::Arrays, Flatten::All].map(parse);
+
+        // ASSERT
+        assert_eq!(
+            result,
+            [
+                (
+                    vec![
+                        EnvVar::new("db__host", "localhost"),
+                        EnvVar::new("hosts", "a,b"),
+                        EnvVar::new("servers", r#"[{"name":"web"}]"#),
+                    ],
+                    vec![]
+                ),
+                (
+                    vec![
+                        EnvVar::new("db", r#"{"host":"localhost"}"#),
+                        EnvVar::new("hosts__0", "a"),
+                        EnvVar::new("hosts__1", "b"),
+                        EnvVar::new("servers__0", r#"{"name":"web"}"#),
+                    ],
+                    vec![]
+                ),
+                (
+                    vec![
+                        EnvVar::new("db__host", "localhost"),
+                        EnvVar::new("hosts__0", "a"),
+                        EnvVar::new("hosts__1", "b"),
+                        EnvVar::new("servers__0__name", "web"),
+                    ],
+                    vec![]
+                ),
+            ]
+        )
+    }
+
+    #[test]
+    fn parse_with_warnings_should_fail_when_too_deep() {
+        // ARRANGE
+        let json = json!({ "a": { "b": [1, 2] }, "c": { "d": { "e": { "f": 1 } } } });
+        let parser = JsonParser::new(ParseOptions::builder().max_depth(3).build());
+
+        // ACT
+        let result = parser.parse_with_warnings(&json);
+
+        // ASSERT
+        assert!(matches!(result, Err(Error::TooDeep { pointer, limit: 3 }) if pointer == "/c/d/e"))
+    }
+
+    #[test]
+    fn parse_with_warnings_should_report_empty_values_and_collisions() {
+        // ARRANGE
+        let json = json!({ "a__b": 1, "a": { "b": 2 }, "empty": {} });
+        let options = ParseOptions::new("__".to_string(), ",".to_string(), false);
+        let parser = JsonParser::new(options);
+
+        // ACT
+        let (_, warnings) = parser.parse_with_warnings(&json).unwrap();
+
+        // ASSERT
+        assert_eq!(
+            warnings,
+            vec![
+                Warning::EmptyValue {
+                    key: "empty".to_string()
+                },
+                Warning::Collision {
+                    key: "a__b".to_string(),
+                    sources: vec!["/a/b".to_string(), "/a__b".to_string()]
+                }
+            ]
+        )
+    }
+
+    #[test]
+    fn parse_with_warnings_should_prefix_root_documents_by_index() {
+        // ARRANGE
+        let json = json!([{ "a": 1 }, { "a": 2, "b": [{ "c": 3 }] }]);
+        let parser = JsonParser::new(ParseOptions::default());
+
+        // ACT
+        let (vars, warnings) = parser.parse_with_warnings(&json).unwrap();
+
+        // ASSERT
+        assert_eq!(
+            vars,
+            vec![
+                EnvVar::new("0__a", 1),
+                EnvVar::new("1__a", 2),
+                EnvVar::new("1__b__0__c", 3)
+            ]
+        );
+        assert_eq!(
+            warnings,
+            vec![Warning::ComplexArray {
+                key: "1__b".to_string()
+            }]
+        )
+    }
+
+    #[test]
+    fn parse_iter_should_yield_variables_in_document_order() {
+        // ARRANGE
+        let json = json!({
+            "db": { "host": "localhost", "port": 5432 },
+            "hosts": ["a", "b"],
+            "servers": [{ "name": "web" }, { "name": "api" }],
+            "empty": {}
+        });
+        let parser = JsonParser::new(ParseOptions::default());
+
+        // ACT
+        let result = parser.parse_iter(&json).collect::<Vec<_>>();
+
+        // ASSERT
+        assert_eq!(
+            result,
+            vec![
+                EnvVar::new("db__host", "localhost"),
+                EnvVar::new("db__port", 5432),
+                EnvVar::new("hosts", "a,b"),
+                EnvVar::new("servers__0__name", "web"),
+                EnvVar::new("servers__1__name", "api"),
+            ]
+        )
+    }
+
+    #[test]
+    fn parse_iter_should_stop_early() {
+        // ARRANGE
+        let json = json!({ "a": 1, "b": 2, "c": 3 });
+        let parser = JsonParser::new(ParseOptions::default());
+
+        // ACT
+        let result = parser.parse_iter(&json).take(1).collect::<Vec<_>>();
+
+        // ASSERT
+        assert_eq!(result, vec![EnvVar::new("a", 1)])
+    }
+
+    #[test]
+    fn transforms_should_rename_rewrite_and_drop_variables() {
+        // ARRANGE
+        let json = json!({ "db": { "host": "localhost", "password": "secret" }, "port": 80 });
+        let parser = JsonParser::new(ParseOptions::default())
+            .with_transform(|var: EnvVar| (var.key() != "db__password").then_some(var))
+            .with_transform(|var: EnvVar| {
+                let (key, value) = var.into_parts();
+                Some(EnvVar::new(key.to_uppercase(), value))
+            });
+
+        // ACT
+        let result = parser.parse(&json);
+
+        // ASSERT
+        assert_eq!(
+            result,
+            vec![
+                EnvVar::new("DB__HOST", "localhost"),
+                EnvVar::new("PORT", 80)
+            ]
+        )
+    }
+
+    #[test]
+    fn parse_owned_should_match_parse() {
+        // ARRANGE
+        let json = json!({
+            "db": { "host": "localhost", "port": 5432 },
+            "hosts": ["a", "b"],
+            "servers": [{ "name": "web" }, { "name": "api" }]
+        });
+        let options = ParseOptions::builder().enumerate_array(true).build();
+        let parser = JsonParser::new(options);
+        let expected = parser.parse(&json);
+
+        // ACT
+        let result = parser.parse_owned(json);
+
+        // ASSERT
+        assert_eq!(result, expected)
+    }
+
+    #[test]
+    fn env_vars_should_sort_by_key_then_value() {
+        // ARRANGE
+        let mut vars = vec![
+            EnvVar::new("B", 1),
+            EnvVar::new("A", "x"),
+            EnvVar::new("A", 2.5),
+            EnvVar::new("A", Value::Null),
+        ];
+
+        // ACT
+        vars.sort();
+
+        // ASSERT
+        assert_eq!(
+            vars,
+            vec![
+                EnvVar::new("A", Value::Null),
+                EnvVar::new("A", 2.5),
+                EnvVar::new("A", "x"),
+                EnvVar::new("B", 1),
+            ]
+        )
+    }
+
+    #[test]
+    fn env_vars_should_deduplicate_in_hash_set() {
+        // ARRANGE
+        let vars = vec![
+            EnvVar::new("A", json!({ "b": [1, 2] })),
+            EnvVar::new("A", json!({ "b": [1, 2] })),
+            EnvVar::new("A", 1),
+            EnvVar::new("A", 1.0),
+        ];
+
+        // ACT
+        let unique = vars.into_iter().collect::<HashSet<_>>();
+
+        // ASSERT
+        assert_eq!(unique.len(), 3)
+    }
+
+    #[cfg(feature = "preserve_order")]
+    #[test]
+    fn env_vars_should_ignore_the_order_of_object_entries() {
+        // ARRANGE
+        let a = EnvVar::new("A", json!({ "x": 1, "y": 2 }));
+        let b = EnvVar::new("A", json!({ "y": 2, "x": 1 }));
+        let hash = |var: &EnvVar| {
+            use std::hash::{Hash, Hasher};
+
+            let mut hasher = std::hash::DefaultHasher::new();
+            var.hash(&mut hasher);
+            hasher.finish()
+        };
+
+        // ACT
+        let ordering = a.cmp(&b);
+
+        // ASSERT
+        assert_eq!(a, b);
+        assert_eq!(ordering, std::cmp::Ordering::Equal);
+        assert_eq!(hash(&a), hash(&b))
+    }
+
+    #[test]
+    fn env_vars_should_collect_into_string_maps() {
+        // ARRANGE
+        let vars = || {
+            vec![
+                EnvVar::new("A", "x"),
+                EnvVar::new("B", 1),
+                EnvVar::new("A", "y"),
+            ]
+        };
+
+        // ACT
+        let sorted = vars().into_iter().collect::<BTreeMap<String, String>>();
+        let hashed = vars().into_iter().collect::<HashMap<String, String>>();
+
+        // ASSERT
+        assert_eq!(
+            sorted.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("A".to_owned(), "y".to_owned()),
+                ("B".to_owned(), "1".to_owned())
+            ]
+        );
+        assert_eq!(hashed.get("A").map(String::as_str), Some("y"))
+    }
+
+    #[test]
+    fn parse_should_record_source_pointers() {
+        // ARRANGE
+        let json = json!({ "servers": [{ "a/b": 1 }], "hosts": ["a", "b"] });
+        let parser = JsonParser::new(ParseOptions::default());
+
+        // ACT
+        let result = parser.parse(&json);
+
+        // ASSERT
+        let pointers = result
+            .iter()
+            .map(EnvVar::source_pointer)
+            .collect::<Vec<_>>();
+
+        assert_eq!(pointers, vec![Some("/hosts"), Some("/servers/0/a~1b")])
+    }
 }
@@ -0,0 +1,137 @@
+use std::fmt::Display;
+
+use crate::{warning::Warning, EnvVar};
+
+/// Size of the environment and arguments of a process on Linux, `ARG_MAX` with the default stack
+pub const LINUX_TOTAL: usize = 2 * 1024 * 1024;
+/// Size of a single `KEY=value` string on Linux, `MAX_ARG_STRLEN`
+pub const LINUX_VARIABLE: usize = 128 * 1024;
+/// Characters of a `KEY=value` string on Windows, counted in UTF-16 units
+pub const WINDOWS_VARIABLE: usize = 32767;
+/// Size of a line of the environment files read by systemd with `EnvironmentFile=`
+pub const SYSTEMD_LINE: usize = 1024 * 1024;
+
+/// Platform limit exceeded, found by [`check`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitKind {
+    /// The environment is larger than [`LINUX_TOTAL`] once the variable is added
+    LinuxTotal,
+    /// The variable is larger than [`LINUX_VARIABLE`]
+    LinuxVariable,
+    /// The variable is longer than [`WINDOWS_VARIABLE`]
+    WindowsVariable,
+    /// The `.env` line of the variable is larger than [`SYSTEMD_LINE`]
+    SystemdLine,
+}
+
+impl Display for LimitKind {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LimitKind::LinuxTotal => write!(
+                fmt,
+                "brings the environment over the {LINUX_TOTAL} bytes allowed by Linux"
+            ),
+            LimitKind::LinuxVariable => {
+                write!(fmt, "is larger than the {LINUX_VARIABLE} bytes allowed by Linux")
+            }
+            LimitKind::WindowsVariable => write!(
+                fmt,
+                "is longer than the {WINDOWS_VARIABLE} characters allowed by Windows"
+            ),
+            LimitKind::SystemdLine => write!(
+                fmt,
+                "is larger than the {SYSTEMD_LINE} bytes allowed in a line of a systemd environment file"
+            ),
+        }
+    }
+}
+
+/// Report the variables exceeding the limits of the platforms they may be loaded on, in their order.
+///
+/// Sizes are measured on the `KEY=value` strings of the process environment, the NUL terminators
+/// included, and on the `.env` lines for systemd. The total size is reported once, for the first
+/// variable exceeding it.
+pub fn check(vars: &[EnvVar]) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    let mut total = 0;
+
+    for var in vars {
+        let warning = |kind| Warning::Limit {
+            key: var.key().to_owned(),
+            kind,
+        };
+
+        let (key, value) = <(String, String)>::from(var);
+        let size = key.len() + 1 + value.len() + 1;
+
+        if total <= LINUX_TOTAL && total + size > LINUX_TOTAL {
+            warnings.push(warning(LimitKind::LinuxTotal));
+        }
+        total += size;
+
+        if size > LINUX_VARIABLE {
+            warnings.push(warning(LimitKind::LinuxVariable));
+        }
+
+        let length = key.encode_utf16().count() + 1 + value.encode_utf16().count() + 1;
+        if length > WINDOWS_VARIABLE {
+            warnings.push(warning(LimitKind::WindowsVariable));
+        }
+
+        // nested values cannot be written to a file anyway
+        if var.render().is_ok_and(|line| line.len() > SYSTEMD_LINE) {
+            warnings.push(warning(LimitKind::SystemdLine));
+        }
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, LimitKind, LINUX_VARIABLE, WINDOWS_VARIABLE};
+    use crate::{warning::Warning, EnvVar};
+
+    #[test]
+    fn check_should_report_the_exceeded_limits() {
+        // ARRANGE
+        let vars = vec![
+            EnvVar::new("SMALL", "a"),
+            EnvVar::new("WINDOWS", "a".repeat(WINDOWS_VARIABLE)),
+            EnvVar::new("LINUX", "a".repeat(LINUX_VARIABLE)),
+        ]
+        .into_iter()
+        .chain((0..20).map(|index| EnvVar::new(format!("BIG_{index}"), "a".repeat(100_000))))
+        .collect::<Vec<_>>();
+
+        // ACT
+        let result = check(&vars);
+
+        // ASSERT
+        let warning = |key: &str, kind| Warning::Limit {
+            key: key.to_owned(),
+            kind,
+        };
+        let total = result
+            .iter()
+            .filter(|warning| {
+                matches!(
+                    warning,
+                    Warning::Limit {
+                        kind: LimitKind::LinuxTotal,
+                        ..
+                    }
+                )
+            })
+            .count();
+        assert_eq!(total, 1);
+        assert_eq!(
+            result[..3],
+            [
+                warning("WINDOWS", LimitKind::WindowsVariable),
+                warning("LINUX", LimitKind::LinuxVariable),
+                warning("LINUX", LimitKind::WindowsVariable),
+            ]
+        )
+    }
+}
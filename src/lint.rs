@@ -0,0 +1,140 @@
+use std::fmt::Display;
+
+use serde_json::Value;
+
+use crate::{warning::Warning, EnvVar};
+
+/// Names longer than this are reported, some shells and platforms truncate or reject them
+pub const MAX_NAME_LENGTH: usize = 128;
+
+/// Variables commonly set by the system or the shell, which generated variables should not replace
+const WELL_KNOWN: [&str; 18] = [
+    "HOME",
+    "HOSTNAME",
+    "IFS",
+    "LANG",
+    "LD_LIBRARY_PATH",
+    "LD_PRELOAD",
+    "LOGNAME",
+    "NODE_OPTIONS",
+    "PATH",
+    "PS1",
+    "PWD",
+    "PYTHONPATH",
+    "SHELL",
+    "TERM",
+    "TMPDIR",
+    "TZ",
+    "USER",
+    "USERNAME",
+];
+
+/// Issue found by [`lint`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintKind {
+    /// The name starts with a digit, which shells reject
+    LeadingDigit,
+    /// The name contains lowercase letters, while variables are conventionally uppercase
+    Lowercase,
+    /// The name is longer than [`MAX_NAME_LENGTH`]
+    LongName,
+    /// The value contains an unresolved `${...}` reference
+    Placeholder,
+    /// The name is a variable set by the system or the shell, like `PATH`
+    WellKnown,
+}
+
+impl Display for LintKind {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LintKind::LeadingDigit => write!(fmt, "starts with a digit"),
+            LintKind::Lowercase => write!(fmt, "is not uppercase"),
+            LintKind::LongName => write!(fmt, "is longer than {MAX_NAME_LENGTH} characters"),
+            LintKind::Placeholder => write!(fmt, "holds an unresolved `${{...}}` placeholder"),
+            LintKind::WellKnown => write!(fmt, "replaces a variable set by the system"),
+        }
+    }
+}
+
+/// Report the suspicious names and values among the variables, in their order
+pub fn lint(vars: &[EnvVar]) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+
+    for var in vars {
+        let key = var.key();
+        let warning = |kind| Warning::Lint {
+            key: key.to_owned(),
+            kind,
+        };
+
+        if key.starts_with(|char: char| char.is_ascii_digit()) {
+            warnings.push(warning(LintKind::LeadingDigit));
+        }
+
+        if key.chars().any(char::is_lowercase) {
+            warnings.push(warning(LintKind::Lowercase));
+        }
+
+        if key.len() > MAX_NAME_LENGTH {
+            warnings.push(warning(LintKind::LongName));
+        }
+
+        if WELL_KNOWN.contains(&key) {
+            warnings.push(warning(LintKind::WellKnown));
+        }
+
+        if let Value::String(value) = var.value() {
+            if is_placeholder(value) {
+                warnings.push(warning(LintKind::Placeholder));
+            }
+        }
+    }
+
+    warnings
+}
+
+fn is_placeholder(value: &str) -> bool {
+    value
+        .split("${")
+        .skip(1)
+        .any(|reference| reference.find('}').is_some_and(|end| end > 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lint, LintKind};
+    use crate::{warning::Warning, EnvVar};
+
+    #[test]
+    fn lint_should_report_suspicious_names_and_values() {
+        // ARRANGE
+        let vars = vec![
+            EnvVar::new("DB__HOST", "localhost"),
+            EnvVar::new("1ST", "a"),
+            EnvVar::new("db__port", 5432),
+            EnvVar::new("X".repeat(129), "a"),
+            EnvVar::new("URL", "https://${HOST}/"),
+            EnvVar::new("EMPTY", "${}"),
+            EnvVar::new("PATH", "/bin"),
+        ];
+
+        // ACT
+        let result = lint(&vars);
+
+        // ASSERT
+        let warning = |key: &str, kind| Warning::Lint {
+            key: key.to_owned(),
+            kind,
+        };
+        assert_eq!(
+            result,
+            vec![
+                warning("1ST", LintKind::LeadingDigit),
+                warning("db__port", LintKind::Lowercase),
+                warning(&"X".repeat(129), LintKind::LongName),
+                warning("URL", LintKind::Placeholder),
+                warning("PATH", LintKind::WellKnown),
+            ]
+        )
+    }
+}
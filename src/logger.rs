@@ -0,0 +1,77 @@
+use std::io::Write;
+
+use clap::ValueEnum;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum LogFormat {
+    /// Human readable messages
+    #[default]
+    Text,
+    /// One JSON object per message
+    Json,
+}
+
+/// Logger writing every message to STDERR
+struct Logger {
+    format: LogFormat,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let message = record.args().to_string();
+        let line = match self.format {
+            LogFormat::Text => {
+                let level = match record.level() {
+                    Level::Error => "Error",
+                    Level::Warn => "Warning",
+                    Level::Info => "Info",
+                    Level::Debug => "Debug",
+                    Level::Trace => "Trace",
+                };
+
+                format!("{level}: {message}")
+            }
+            LogFormat::Json => serde_json::json!({
+                "level": record.level().as_str().to_lowercase(),
+                "message": message,
+            })
+            .to_string(),
+        };
+
+        let _ = writeln!(std::io::stderr().lock(), "{line}");
+    }
+
+    fn flush(&self) {
+        let _ = std::io::stderr().flush();
+    }
+}
+
+/// Install the logger, showing warnings and errors by default.
+/// Each `verbose` step enables a more detailed level, each `quiet` step hides one.
+pub fn init(verbose: u8, quiet: u8, format: LogFormat) {
+    let levels = [
+        LevelFilter::Off,
+        LevelFilter::Error,
+        LevelFilter::Warn,
+        LevelFilter::Info,
+        LevelFilter::Debug,
+        LevelFilter::Trace,
+    ];
+
+    let level = (2 + verbose as usize)
+        .saturating_sub(quiet as usize)
+        .min(levels.len() - 1);
+
+    if log::set_boxed_logger(Box::new(Logger { format })).is_ok() {
+        log::set_max_level(levels[level]);
+    }
+}
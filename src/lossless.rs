@@ -0,0 +1,393 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{dotenv, EnvVar, Error, JsonParser};
+
+/// Prefix of the comments holding the metadata of the following variable
+const PREFIX: &str = "# json2env:";
+
+/// Step from a JSON value to one of its children
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Segment {
+    Index(usize),
+    Key(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Type {
+    Null,
+    Bool,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+impl Type {
+    fn of(value: &Value) -> Self {
+        match value {
+            Value::Null => Type::Null,
+            Value::Bool(_) => Type::Bool,
+            Value::Number(_) => Type::Number,
+            Value::String(_) => Type::String,
+            Value::Array(_) => Type::Array,
+            Value::Object(_) => Type::Object,
+        }
+    }
+}
+
+/// Where a variable, or an empty value producing none, comes from in the original document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Metadata {
+    path: Vec<Segment>,
+    #[serde(rename = "type")]
+    kind: Type,
+    /// Variable holding the value, missing for empty objects and arrays
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
+    /// Original value, recorded only when it cannot be recovered from the variable
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    value: Option<Value>,
+}
+
+/// Render the variables parsed from the document as a `.env` file where every variable is
+/// preceded by a metadata comment, recording everything needed by [`restore`] to rebuild the exact
+/// original document.
+///
+/// The metadata is found in the document at the source pointer of each variable, so the variables
+/// can come from any options of the parser and be renamed or requoted afterwards. The values they
+/// do not hold, like the empty objects that are skipped, are recorded on their own.
+pub fn render(json: &Value, vars: &[EnvVar]) -> String {
+    let mut nodes = Vec::new();
+    visit(json, &mut String::new(), &mut nodes);
+    let positions = nodes
+        .iter()
+        .enumerate()
+        .map(|(position, (pointer, _))| (pointer.as_str(), position))
+        .collect::<HashMap<_, _>>();
+
+    // variables of a whole container, like the length of an enumerated array, hold no element
+    let mut sources = HashSet::new();
+    let mut containers = HashSet::new();
+    for pointer in vars.iter().filter_map(EnvVar::source_pointer) {
+        sources.insert(pointer);
+        containers.extend(pointer.match_indices('/').map(|(end, _)| &pointer[..end]));
+    }
+
+    let mut seen = HashSet::new();
+    let duplicates = vars
+        .iter()
+        .filter(|var| !seen.insert(var.key()))
+        .map(EnvVar::key)
+        .collect::<HashSet<_>>();
+
+    let mut entries = Vec::with_capacity(vars.len());
+    for var in vars {
+        let pointer = var.source_pointer();
+        let node = pointer.and_then(|pointer| positions.get(pointer)).copied();
+
+        let element = pointer.is_some_and(|pointer| !containers.contains(pointer));
+        let metadata = node.filter(|_| element).map(|position| {
+            let (ref pointer, value) = nodes[position];
+
+            // colliding keys and values mangled by the format are kept in the metadata
+            let kind = Type::of(value);
+            let recovered =
+                !duplicates.contains(var.key()) && read_back(var, kind).as_ref() == Some(value);
+            Metadata {
+                path: path(json, pointer),
+                kind,
+                key: Some(var.key().to_owned()),
+                value: (!recovered).then(|| value.clone()),
+            }
+        });
+
+        entries.push((node, metadata, Some(var)));
+    }
+
+    // empty objects and arrays that are not held by a variable
+    for (position, (pointer, value)) in nodes.iter().enumerate() {
+        let empty = match value {
+            Value::Object(object) => object.is_empty(),
+            Value::Array(array) => array.is_empty(),
+            _ => false,
+        };
+
+        if empty && !sources.contains(pointer.as_str()) {
+            let metadata = Metadata {
+                path: path(json, pointer),
+                kind: Type::of(value),
+                key: None,
+                value: None,
+            };
+            entries.push((Some(position), Some(metadata), None));
+        }
+    }
+
+    // the metadata follows the document order, in which the restored objects get their keys
+    entries.sort_by_key(|(position, ..)| position.unwrap_or(usize::MAX));
+
+    let mut lines = Vec::with_capacity(entries.len() * 2);
+    for (_, metadata, var) in entries {
+        if let Some(metadata) = metadata {
+            let metadata =
+                serde_json::to_string(&metadata).expect("metadata is always serializable");
+            lines.push(format!("{PREFIX}{metadata}"));
+        }
+
+        if let Some(var) = var {
+            lines.push(var.to_string());
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// Rebuild the original document from a `.env` file produced by [`render`].
+///
+/// Returns `None` if the file does not contain any metadata, fails if a variable referenced by the
+/// metadata is missing or its value does not match the recorded type.
+pub fn restore(input: &str) -> Result<Option<Value>, Error> {
+    let entries = input
+        .lines()
+        .filter_map(|line| line.trim_start().strip_prefix(PREFIX))
+        .map(serde_json::from_str::<Metadata>)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if entries.is_empty() {
+        return Ok(None);
+    }
+
+    let vars = dotenv::parse(input).into_iter().collect::<HashMap<_, _>>();
+    let mut root = Value::Null;
+
+    for metadata in entries {
+        let value = match (metadata.value, metadata.key) {
+            (Some(value), _) => value,
+            (None, Some(key)) => {
+                let value = vars
+                    .get(&key)
+                    .ok_or_else(|| Error::InvalidInput(format!("`{key}` is missing").into()))?;
+
+                typed(value, metadata.kind).ok_or_else(|| {
+                    Error::InvalidInput(format!("`{key}` does not hold the recorded type").into())
+                })?
+            }
+            (None, None) => match metadata.kind {
+                Type::Array => Value::Array(vec![]),
+                _ => Value::Object(Map::new()),
+            },
+        };
+
+        insert(&mut root, &metadata.path, value);
+    }
+
+    Ok(Some(root))
+}
+
+/// Collect the JSON pointer of every value of the document with the value, in document order
+fn visit<'a>(value: &'a Value, pointer: &mut String, nodes: &mut Vec<(String, &'a Value)>) {
+    nodes.push((pointer.clone(), value));
+
+    let length = pointer.len();
+    match value {
+        Value::Array(array) => {
+            for (index, item) in array.iter().enumerate() {
+                JsonParser::push_pointer_segment(pointer, &index.to_string());
+                visit(item, pointer, nodes);
+                pointer.truncate(length);
+            }
+        }
+        Value::Object(object) => {
+            for (name, item) in object.iter() {
+                JsonParser::push_pointer_segment(pointer, name);
+                visit(item, pointer, nodes);
+                pointer.truncate(length);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Steps to the value at the JSON pointer of the document, telling array indices apart from
+/// numeric object keys
+fn path(json: &Value, pointer: &str) -> Vec<Segment> {
+    let mut path = Vec::new();
+    let mut value = json;
+
+    for segment in pointer.split('/').skip(1) {
+        let name = segment.replace("~1", "/").replace("~0", "~");
+        match value {
+            Value::Array(array) => {
+                let index = name.parse().expect("the pointer comes from the document");
+                value = &array[index];
+                path.push(Segment::Index(index));
+            }
+            _ => {
+                value = &value[name.as_str()];
+                path.push(Segment::Key(name));
+            }
+        }
+    }
+
+    path
+}
+
+/// Value of the variable read back from its `.env` line with the original type, if it can be
+fn read_back(var: &EnvVar, kind: Type) -> Option<Value> {
+    match dotenv::parse(&var.to_string()).as_slice() {
+        [(key, value)] if key == var.key() => typed(value, kind),
+        _ => None,
+    }
+}
+
+/// Convert the value read from the `.env` file back to its original JSON type
+fn typed(value: &str, kind: Type) -> Option<Value> {
+    match kind {
+        Type::String => Some(Value::String(value.to_owned())),
+        Type::Null | Type::Bool | Type::Number => {
+            let value: Value = serde_json::from_str(value).ok()?;
+
+            let matches = match kind {
+                Type::Null => value.is_null(),
+                Type::Bool => value.is_boolean(),
+                _ => value.is_number(),
+            };
+
+            matches.then_some(value)
+        }
+        Type::Array | Type::Object => None,
+    }
+}
+
+/// Set the value at the path, creating the missing parents along the way
+fn insert(root: &mut Value, path: &[Segment], value: Value) {
+    let Some((segment, rest)) = path.split_first() else {
+        *root = value;
+        return;
+    };
+
+    let child = match segment {
+        Segment::Index(index) => {
+            if !root.is_array() {
+                *root = Value::Array(vec![]);
+            }
+
+            let array = root
+                .as_array_mut()
+                .expect("root has just been made an array");
+            if array.len() <= *index {
+                array.resize(index + 1, Value::Null);
+            }
+
+            &mut array[*index]
+        }
+        Segment::Key(key) => {
+            if !root.is_object() {
+                *root = Value::Object(Map::new());
+            }
+
+            let object = root
+                .as_object_mut()
+                .expect("root has just been made an object");
+            object.entry(key.clone()).or_insert(Value::Null)
+        }
+    };
+
+    insert(child, rest, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{render, restore};
+    use crate::{EmptyValues, EnvVar, Flatten, JsonParser, ParseOptions};
+
+    fn round_trip(json: serde_json::Value, parser: &JsonParser) -> serde_json::Value {
+        let vars = parser.parse(&json);
+        let rendered = render(&json, &vars);
+
+        restore(&rendered).unwrap().unwrap()
+    }
+
+    fn parser(enumerate_array: bool) -> JsonParser {
+        JsonParser::new(ParseOptions::new(
+            "__".to_owned(),
+            ",".to_owned(),
+            enumerate_array,
+        ))
+    }
+
+    #[test]
+    fn restore_should_rebuild_the_exact_document() {
+        // ARRANGE
+        let json = json!({
+            "db": { "host": "localhost", "port": 5432, "ssl": false, "password": null },
+            "hosts": ["a", "b"],
+            "servers": [{ "name": "web" }],
+            "numeric": { "0": "not an array" },
+            "empty": { "object": {}, "array": [] },
+            "text": "ends with \\",
+            "a__b": "collides",
+            "a": { "b": "with this" }
+        });
+
+        // ACT
+        let joined = round_trip(json.clone(), &parser(false));
+        let enumerated = round_trip(json.clone(), &parser(true));
+
+        // ASSERT
+        assert_eq!(joined, json);
+        assert_eq!(enumerated, json)
+    }
+
+    #[test]
+    fn restore_should_rebuild_the_document_whatever_the_options() {
+        // ARRANGE
+        let json = json!({
+            "db": { "host": "localhost", "port": 5432 },
+            "hosts": ["a", "b"],
+            "servers": [{ "name": "web", "tags": [] }],
+            "empty": {}
+        });
+        let counted = ParseOptions::builder()
+            .enumerate_array(true)
+            .array_length("COUNT")
+            .index_start(1)
+            .empty_values(EmptyValues::Literal)
+            .build();
+        let counted = JsonParser::new(counted).with_transform(|var: EnvVar| {
+            let key = var.key().to_uppercase();
+            Some(var.with_key(key))
+        });
+        let kept = ParseOptions::builder()
+            .flatten(Flatten::Objects)
+            .key_separator_levels(["."])
+            .build();
+
+        // ACT
+        let counted = round_trip(json.clone(), &counted);
+        let kept = round_trip(json.clone(), &JsonParser::new(kept));
+
+        // ASSERT
+        assert_eq!(counted, json);
+        assert_eq!(kept, json)
+    }
+
+    #[test]
+    fn restore_should_return_none_without_metadata() {
+        // ARRANGE
+        let input = "KEY=value";
+
+        // ACT
+        let result = restore(input).unwrap();
+
+        // ASSERT
+        assert_eq!(result, None)
+    }
+}
@@ -1,89 +1,2995 @@
-use std::{
-    error::Error,
-    fs::File,
-    io::{BufRead, BufReader, BufWriter, Read, Write},
-};
-
-use clap::Parser;
-use json2env::{JsonParser, ParseOptions};
-use serde_json::Value;
-
-fn main() -> Result<(), Box<dyn Error>> {
-    let args = Args::parse();
-
-    let mut reader: Box<dyn BufRead> = match args.input {
-        None => Box::new(std::io::stdin().lock()),
-        Some(ref filename) => {
-            let file = File::open(filename)
-                .inspect_err(|_| eprintln!("Error: Could not open file `{filename}`"))?;
-
-            Box::new(BufReader::new(file))
-        }
-    };
-
-    let mut buffer = String::new();
-
-    let input = args.input.unwrap_or("STDIN".to_string());
-    reader
-        .read_to_string(&mut buffer)
-        .inspect_err(|_| eprintln!("Error: Could not read `{input}`"))?;
-
-    let json: Value = serde_json::from_str(&buffer)
-        .inspect_err(|_| eprintln!("Error: `{input}` does not contain valid JSON"))?;
-
-    let options = ParseOptions::new(
-        args.key_separator,
-        args.array_separator,
-        args.enumerate_array,
-    );
-
-    let mut parser = JsonParser::new(options);
-    let keys = parser.parse(&json);
-
-    let environ = keys
-        .iter()
-        .map(ToString::to_string)
-        .collect::<Vec<String>>()
-        .join("\n");
-
-    let mut writer: Box<dyn Write> = match args.output {
-        None => Box::new(std::io::stdout().lock()),
-        Some(ref filename) => {
-            let file = File::create(filename)
-                .inspect_err(|_| eprintln!("Error: Could not open file `{filename}`"))?;
-
-            Box::new(BufWriter::new(file))
-        }
-    };
-
-    let output = args.output.unwrap_or("STDOUT".to_string());
-    writer
-        .write_all(environ.as_bytes())
-        .inspect_err(|_| eprintln!("Error: Could not write to `{output}`"))?;
-
-    Ok(())
-}
-
-#[derive(Debug, Parser)]
-#[command(name = "json2env", version, about)]
-struct Args {
-    /// Input file, defaults to STDIN if not specified
-    #[arg(short, long, value_name = "FILE")]
-    input: Option<String>,
-
-    /// Output file, defaults to STDOUT if not specified
-    #[arg(short, long, value_name = "FILE")]
-    output: Option<String>,
-
-    /// Separator for nested keys
-    #[arg(short = 's', long, value_name = "STRING", default_value = "__")]
-    key_separator: String,
-
-    /// Separator for array elements
-    #[arg(short = 'S', long, value_name = "STRING", default_value = ",")]
-    array_separator: String,
-
-    /// Separate array elements in multiple environment variables
-    #[arg(short, long)]
-    enumerate_array: bool,
-}
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, IsTerminal, Read, Write},
+    path::Path,
+    process::ExitCode,
+    time::{Duration, SystemTime},
+};
+
+use chrono::{DateTime, SecondsFormat};
+use clap::{
+    builder::{PossibleValue, PossibleValuesParser},
+    parser::ValueSource,
+    ArgAction, ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum,
+    ValueHint,
+};
+use clap_complete::Shell;
+use config::Config;
+use json2env::{
+    annotation,
+    cast::{self, Cast},
+    diff::{self, EnvDiff},
+    dotenv, input, limit, lossless,
+    map::EnvMap,
+    merge::{self, Precedence},
+    redact::{Redaction, REDACTED},
+    render::{self, Format},
+    secret, stream, substitute,
+    target::Target,
+    timestamp::{self, TimestampFormat},
+    types,
+    unflatten::{self, UnflattenOptions},
+    warning::Warning,
+    EmptyValues, EnvVar, Error, Flatten, IndexFormat, JsonParser, ParseOptions,
+};
+use logger::LogFormat;
+use rayon::prelude::*;
+use regex::Regex;
+use serde_json::Value;
+use sha2::Digest as _;
+
+mod clipboard;
+mod config;
+mod interactive;
+mod logger;
+mod schema;
+mod sops;
+
+fn main() -> ExitCode {
+    let matches = Args::command().get_matches();
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|error| error.exit());
+
+    logger::init(args.verbose, args.quiet, args.log_format);
+
+    match run(args, &matches) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            match error {
+                Error::Drift => log::info!("{error}"),
+                _ => log::error!("{error}"),
+            }
+
+            ExitCode::from(error.exit_code())
+        }
+    }
+}
+
+fn run(mut args: Args, matches: &ArgMatches) -> Result<(), Error> {
+    let config = Config::load(args.config.as_deref())?;
+    match (&mut args.command, matches.subcommand()) {
+        (Some(Command::Diff(diff)), Some((_, matches))) => {
+            diff.parse.configure(&config, matches)?
+        }
+        (Some(Command::Exec(exec)), Some((_, matches))) => {
+            exec.parse.configure(&config, matches)?
+        }
+        (Some(Command::Lint(lint)), Some((_, matches))) => {
+            lint.parse.configure(&config, matches)?
+        }
+        (Some(Command::Batch(batch)), Some((_, matches))) => {
+            batch.parse.configure(&config, matches)?
+        }
+        (Some(Command::Schema(schema)), Some((_, matches))) => {
+            schema.parse.configure(&config, matches)?
+        }
+        (Some(Command::Unflatten(unflatten)), Some((_, matches))) => {
+            if let Some(ref separator) = config.key_separator {
+                if matches.value_source("key_separator") != Some(ValueSource::CommandLine) {
+                    unflatten.key_separator = separator.clone();
+                }
+            }
+
+            if let Some(infer) = config.infer_types {
+                let is_default = |id| matches.value_source(id) != Some(ValueSource::CommandLine);
+
+                if is_default("infer_types") && is_default("no_infer_types") {
+                    unflatten.infer_types = infer;
+                }
+            }
+        }
+        (None, _) => args.configure(&config, matches)?,
+        _ => {}
+    }
+
+    // reading from an interactive terminal is most likely a mistake, show how to use the tool instead
+    let input = match args.command {
+        Some(Command::Exec(ref exec)) => Some(exec.input.as_deref()),
+        Some(Command::Lint(ref lint)) => Some(lint.input.as_deref()),
+        Some(Command::Schema(ref schema)) => Some(schema.input.as_deref()),
+        Some(Command::Unflatten(ref unflatten)) if !unflatten.from_env => {
+            Some(unflatten.input.as_deref())
+        }
+        None if args.cmd.is_none() && !args.from_clipboard => {
+            Some(args.input.first().map(String::as_str))
+        }
+        _ => None,
+    };
+
+    if input == Some(None) && !args.stdin && std::io::stdin().is_terminal() {
+        let mut command = Args::command();
+        command.build();
+
+        let command = match matches.subcommand_name() {
+            Some(name) => command
+                .find_subcommand_mut(name)
+                .expect("subcommand exists"),
+            None => &mut command,
+        };
+
+        command.print_help()?;
+        std::process::exit(2);
+    }
+
+    // multiple inputs are merged after being parsed, the document of each one is not kept
+    if args.input.len() > 1 && (args.lossless || args.stream) {
+        return Err(Error::InvalidOptions(
+            "`--lossless` and `--stream` take a single input".to_owned(),
+        ));
+    }
+
+    match args.command {
+        Some(Command::Diff(args)) => diff(args),
+        Some(Command::Exec(args)) => exec(args),
+        Some(Command::Unflatten(args)) => unflatten(args),
+        Some(Command::Lint(args)) => lint(args),
+        Some(Command::Batch(batch_args)) => batch(batch_args, args.log_format),
+        Some(Command::Schema(args)) => schema(args),
+        Some(Command::Completions { shell }) => {
+            let mut command = completion_command(&config);
+            clap_complete::generate(shell, &mut command, "json2env", &mut std::io::stdout());
+            Ok(())
+        }
+        None if args.watch => watch(&args),
+        None if args.stream => stream(&args),
+        None => convert(&args),
+    }
+}
+
+fn convert(args: &Args) -> Result<(), Error> {
+    check_format_options(args)?;
+
+    // the pattern may come from the configuration, the dropped values could not be restored
+    if args.lossless && args.parse.exclude_value.is_some() {
+        let message = "`--lossless` cannot drop the values matching `--exclude-value`";
+        return Err(Error::InvalidOptions(message.to_owned()));
+    }
+
+    // the format may come from the configuration, the updated file keeps its `.env` lines
+    if args.update.is_some()
+        && args
+            .format
+            .is_some_and(|format| format != FormatArg::Dotenv)
+    {
+        let message = "`--update` only writes the `dotenv` format";
+        return Err(Error::InvalidOptions(message.to_owned()));
+    }
+
+    // comment lines would corrupt the formats read verbatim, like the UTF-16 `.reg` files
+    if args.header {
+        let ci = args.ci.map(Ci::resolve).transpose()?;
+        if !output_format(ci, args).holds_comments() {
+            let message = "The output format cannot hold the `--header` comments";
+            return Err(Error::InvalidOptions(message.to_owned()));
+        }
+    }
+
+    if args.sections {
+        return sections(args);
+    }
+
+    let (mut keys, warnings, document, annotations) = match args.input.as_slice() {
+        [] | [_] if args.concatenated => {
+            let (keys, warnings) = parse_documents(args.input.first().map(String::as_str), args)?;
+            (keys, warnings, None, Vec::new())
+        }
+        [] | [_] => {
+            let mut json = match args.cmd {
+                Some(ref command) => command_json(command, &args.parse)?,
+                None if args.from_clipboard => {
+                    let json = parse_json(clipboard::read()?, "the clipboard", false, &args.parse)?;
+                    prepare(json, None, &args.parse)?
+                }
+                None => read_json(args.input.first().map(String::as_str), &args.parse)?,
+            };
+
+            if let Some(ref template) = args.split_array {
+                return split_array(args, json, template);
+            }
+
+            if let Some(ref directory) = args.output_dir {
+                return split_by_top_level(args, json, directory);
+            }
+
+            let annotations = match args.annotation_comments || args.parse.annotations {
+                true => annotation::strip(&mut json, &args.parse.annotation_prefix),
+                false => Vec::new(),
+            };
+
+            // only the lossless comments need the document, the parser can take it over otherwise
+            let document = args.lossless.then(|| json.clone());
+            let (keys, warnings) = args.parse.parse_with_warnings(json)?;
+
+            (keys, warnings, document, annotations)
+        }
+        inputs => {
+            let (keys, warnings) = parse_all(inputs, &args.parse)?;
+            (keys, warnings, None, Vec::new())
+        }
+    };
+
+    if args.interactive {
+        let redaction = redaction(args.redact_file.as_deref())?;
+        keys = interactive::select(keys, redaction)?;
+    }
+
+    if let Some(ref filename) = args.base {
+        let base = std::fs::read_to_string(filename)
+            .inspect_err(|_| log::error!("Could not read `{filename}`"))?;
+
+        let precedence = match args.base_wins {
+            true => Precedence::Other,
+            false => Precedence::Generated,
+        };
+
+        keys = merge::merge(keys, dotenv::parse(&base), precedence);
+    }
+
+    if args.inherit_env {
+        let precedence = match args.env_wins {
+            true => Precedence::Other,
+            false => Precedence::Generated,
+        };
+
+        keys = merge::merge(keys, environment(), precedence);
+    }
+
+    check_generated(&keys, &required(args)?, args)?;
+
+    if let Some(ref filename) = args.changed_since {
+        let baseline = std::fs::read_to_string(filename)
+            .inspect_err(|_| log::error!("Could not read `{filename}`"))?;
+
+        keys = diff::changed(keys, &dotenv::parse(&baseline));
+    }
+
+    keys = output_vars(keys, args)?;
+
+    // the metadata is found in the document at the source of the variables as they are written
+    let lossless = document.map(|json| lossless::render(&json, &keys));
+
+    if let Some(ref filename) = args.diff {
+        let existing = std::fs::read_to_string(filename)
+            .inspect_err(|_| log::error!("Could not read `{filename}`"))?;
+
+        let existing = dotenv::parse(&existing);
+        let generated = keys.iter().map(Into::into).collect::<Vec<_>>();
+
+        let redaction = redaction(args.redact_file.as_deref())?;
+        let diff = EnvDiff::new(&existing, &generated);
+        return print_diff(&diff, args.mask_values, redaction.as_ref());
+    }
+
+    if let Some(ref filename) = args.update {
+        let existing = match std::fs::read_to_string(filename) {
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => String::new(),
+            result => result.inspect_err(|_| log::error!("Could not read `{filename}`"))?,
+        };
+
+        let updated = dotenv::update(&existing, &keys);
+        std::fs::write(filename, &updated)
+            .inspect_err(|_| log::error!("Could not write to `{filename}`"))?;
+
+        if args.stats {
+            Stats::new(&keys, &warnings, updated.len()).print(args.log_format);
+        }
+
+        return Ok(());
+    }
+
+    let ci = args.ci.map(Ci::resolve).transpose()?;
+    let format = output_format(ci, args);
+
+    if args.to_clipboard {
+        let mut buffer = Vec::new();
+        match args.annotate {
+            true => render::render_annotated_to(&keys, &format, &mut buffer)?,
+            false => render::render_to(&keys, &format, &mut buffer)?,
+        }
+
+        let text = String::from_utf8(buffer).map_err(|_| {
+            Error::InvalidOptions("The output format cannot be copied as text".to_owned())
+        })?;
+        return clipboard::write(text);
+    }
+
+    // files read back by the CI system are appended to, they may hold the variables of other steps
+    let appended = match ci.and_then(Ci::file).filter(|_| args.output.is_none()) {
+        Some(name) => Some(std::env::var(name).map_err(|_| {
+            Error::InvalidOptions(format!("`{name}` must be set to write the variables to it"))
+        })?),
+        None => None,
+    };
+
+    // encrypted before the output is opened, so that a failure leaves it untouched
+    let encrypted = match args.sops_age.is_empty() && args.sops_kms.is_empty() {
+        true => None,
+        false => {
+            let mut plain = Vec::new();
+            match args.annotate {
+                true => render::render_annotated_to(&keys, &format, &mut plain)?,
+                false => render::render_to(&keys, &format, &mut plain)?,
+            }
+            plain.push(b'\n');
+            Some(sops::encrypt(&plain, &args.sops_age, &args.sops_kms)?)
+        }
+    };
+
+    let output = appended.as_deref().or(args.output.as_deref());
+    let opened = match appended {
+        Some(ref filename) => open_append(filename)?,
+        None => open_output(output)?,
+    };
+
+    // only what people may see is redacted, files keep the real values
+    let console = appended.is_none() && args.output.is_none() && std::io::stdout().is_terminal();
+    let redacted = match redaction(args.redact_file.as_deref())? {
+        Some(redaction) if console => Some(redaction.redact(&keys)),
+        _ => None,
+    };
+
+    let mut writer = CountingWriter::new(opened);
+    let written = (|| {
+        if args.header {
+            writer.write_all(header(&args.input).as_bytes())?;
+        }
+
+        match (&lossless, &encrypted) {
+            (Some(content), _) => writer.write_all(content.as_bytes())?,
+            (None, Some(content)) => writer.write_all(content)?,
+            (None, None) => {
+                let shown = redacted.as_deref().unwrap_or(&keys);
+                match (args.annotation_comments, args.annotate) {
+                    (true, _) => annotation::render(shown, &annotations, &mut writer)?,
+                    (false, true) => render::render_annotated_to(shown, &format, &mut writer)?,
+                    (false, false) => render::render_to(shown, &format, &mut writer)?,
+                }
+            }
+        }
+
+        if appended.is_some() && !keys.is_empty() {
+            writer.write_all(b"\n")?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    })();
+
+    written.inspect_err(|error| {
+        if let Error::Io(_) = error {
+            log::error!("Could not write to `{}`", output.unwrap_or("STDOUT"))
+        }
+    })?;
+
+    // encrypted and hashed values are not secret anymore, redacted ones were not shown
+    let hidden = encrypted.is_some() || args.hash_values.is_some();
+    if !args.no_secret_warnings && !hidden {
+        let shown = redacted
+            .as_deref()
+            .unwrap_or(&keys)
+            .iter()
+            .filter(|var| *var.value() != REDACTED)
+            .cloned()
+            .collect::<Vec<_>>();
+        let secrets = secret::detect(&shown);
+
+        // build arguments are recorded in the image history wherever they are written
+        let (exposed, consequence) = match (&format, output) {
+            (Format::BuildArgs { .. }, _) => (true, "would be recorded in the image history"),
+            (_, None) => (true, "has been written to STDOUT"),
+            (_, Some(filename)) => (world_readable(filename), "can be read by other users"),
+        };
+
+        if exposed {
+            for warning in secrets.iter() {
+                log::warn!("{warning} and {consequence}");
+            }
+
+            if let (Format::BuildArgs { .. }, false) = (&format, secrets.is_empty()) {
+                log::warn!(
+                    "Pass secrets to `docker build` with `--secret` instead of build arguments"
+                );
+            }
+        }
+    }
+
+    if args.stats {
+        Stats::new(&keys, &warnings, writer.bytes).print(args.log_format);
+    }
+
+    Ok(())
+}
+
+/// Command line definition completing `--preset` with the built-in presets and the ones of the
+/// configuration file, which the definition cannot know on its own
+fn completion_command(config: &Config) -> clap::Command {
+    let presets = Preset::value_variants()
+        .iter()
+        .filter_map(Preset::to_possible_value)
+        .chain(config.presets.keys().cloned().map(PossibleValue::new))
+        .collect::<Vec<_>>();
+    let complete = |arg: clap::Arg| arg.value_parser(PossibleValuesParser::new(presets.clone()));
+
+    let mut command = Args::command().mut_arg("preset", complete);
+    let subcommands = command
+        .get_subcommands()
+        .filter(|subcommand| {
+            subcommand
+                .get_arguments()
+                .any(|arg| arg.get_id() == "preset")
+        })
+        .map(|subcommand| subcommand.get_name().to_owned())
+        .collect::<Vec<_>>();
+    for name in subcommands {
+        command = command.mut_subcommand(name, |subcommand| subcommand.mut_arg("preset", complete));
+    }
+
+    command
+}
+
+/// Fail if an option of an output format is given for another format, which would ignore it. The
+/// format may come from the configuration, the command line parser cannot tell.
+fn check_format_options(args: &Args) -> Result<(), Error> {
+    let options = [
+        ("--single-line", args.single_line, FormatArg::BuildArgs),
+        ("--readonly", args.readonly, FormatArg::Export),
+        (
+            "--reload-name",
+            args.reload_name.is_some(),
+            FormatArg::Reload,
+        ),
+    ];
+
+    for (option, given, format) in options {
+        if given && args.format != Some(format) {
+            let format = format.to_possible_value().expect("no format is skipped");
+            let message = format!("`{option}` requires the `{}` format", format.get_name());
+            return Err(Error::InvalidOptions(message));
+        }
+    }
+
+    Ok(())
+}
+
+/// Fail if no variables were generated with `--fail-if-empty`, or if a required one is missing
+fn check_generated(keys: &[EnvVar], required: &[String], args: &Args) -> Result<(), Error> {
+    if args.fail_if_empty && keys.is_empty() {
+        return Err(Error::Validation("No variables were generated".to_owned()));
+    }
+
+    let present = keys
+        .iter()
+        .filter(|var| !is_empty(var))
+        .map(EnvVar::key)
+        .collect::<HashSet<_>>();
+    check_required(required, |key| present.contains(key))
+}
+
+/// The variables as they are written, typed, hashed and quoted, failing with `--strict` if they
+/// exceed the size limits
+fn output_vars(keys: Vec<EnvVar>, args: &Args) -> Result<Vec<EnvVar>, Error> {
+    let mut keys = typed_and_hashed(keys, args);
+
+    if args.quoting != Quoting::Strings {
+        keys = keys
+            .into_iter()
+            .map(|var| args.quoting.quote(var))
+            .collect();
+    }
+
+    // oversized environments only fail once loaded, the platform limits are always checked
+    let exceeded = limit::check(&keys);
+    for warning in exceeded.iter() {
+        match args.strict {
+            true => log::error!("{warning}"),
+            false => log::warn!("{warning}"),
+        }
+    }
+
+    match (args.strict, exceeded.len()) {
+        (false, _) | (true, 0) => Ok(keys),
+        (true, 1) => Err(Error::Validation("Found 1 exceeded limit".to_owned())),
+        (true, count) => Err(Error::Validation(format!("Found {count} exceeded limits"))),
+    }
+}
+
+/// The variables with the types of `--emit-types` and the digests of `--hash-values`, which only
+/// replace the values of the document, not their types
+fn typed_and_hashed(keys: Vec<EnvVar>, args: &Args) -> Vec<EnvVar> {
+    let hash = |var| match args.hash_values {
+        Some(digest) => digest.hash(var),
+        None => var,
+    };
+
+    match args.emit_types {
+        Some(TypesArg::Companions) => {
+            let suffix = format!("{}TYPE", args.parse.key_separator);
+            let typed = types::companions(keys, &suffix);
+
+            // each variable is followed by its companion
+            typed
+                .into_iter()
+                .enumerate()
+                .map(|(index, var)| if index % 2 == 0 { hash(var) } else { var })
+                .collect()
+        }
+        Some(TypesArg::Manifest) => {
+            let manifest = types::manifest(&keys);
+            keys.into_iter().map(hash).chain([manifest]).collect()
+        }
+        None => keys.into_iter().map(hash).collect(),
+    }
+}
+
+/// Format of the variables written by [`convert`], the one of the CI system taking precedence
+fn output_format(ci: Option<Ci>, args: &Args) -> Format {
+    // the preview is meant for people only, scripts and files always get the plain format
+    let preview = args.pretty && args.output.is_none() && std::io::stdout().is_terminal();
+    match (ci, args.format) {
+        (Some(ci), _) => ci.format(),
+        (None, Some(format)) => format.format(args),
+        (None, None) if preview => {
+            let color = std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty());
+            Format::Pretty { color }
+        }
+        (None, None) if args.group => Format::Grouped {
+            // the sections are named by the first level of the keys
+            key_separator: (args.parse.key_separator_levels.first())
+                .unwrap_or(&args.parse.key_separator)
+                .clone(),
+        },
+        (None, None) => Format::Dotenv,
+    }
+}
+
+/// Convert each element of the root array as a separate document, written to the file named by
+/// the template with `{i}` replaced by its index. The variables of each file are checked and
+/// written like the ones of [`convert`].
+fn split_array(args: &Args, json: Value, template: &str) -> Result<(), Error> {
+    let Value::Array(documents) = json else {
+        let message = "The root of the input must be an array to be split";
+        return Err(Error::InvalidInput(message.into()));
+    };
+
+    let format = args
+        .format
+        .map_or(Format::Dotenv, |format| format.format(args));
+    let required = required(args)?;
+
+    for (index, document) in documents.into_iter().enumerate() {
+        if !document.is_object() {
+            let message = format!("The element {index} of the root array is not an object");
+            return Err(Error::InvalidInput(message.into()));
+        }
+
+        let vars = args.parse.parse(document)?;
+        check_generated(&vars, &required, args)?;
+        let vars = output_vars(vars, args)?;
+
+        let index = index + args.parse.array_index_start;
+        write_vars(&template.replace("{i}", &index.to_string()), &vars, &format)?;
+    }
+
+    Ok(())
+}
+
+/// Convert each object at the top level as a separate document, written to the file of the
+/// directory named by its key, like `envs/dev.env` for `dev`, checked and written like the
+/// variables of [`split_array`]
+fn split_by_top_level(args: &Args, json: Value, directory: &str) -> Result<(), Error> {
+    let Value::Object(sections) = json else {
+        let message = "The root of the input must be an object to be split";
+        return Err(Error::InvalidInput(message.into()));
+    };
+
+    // keys become file names, they must not reach outside of the directory
+    let invalid = sections.iter().find(|(key, value)| {
+        !value.is_object() || key.is_empty() || key.contains(['/', '\\']) || key.starts_with('.')
+    });
+    if let Some((key, value)) = invalid {
+        let message = match value.is_object() {
+            true => format!("`{key}` cannot be used as a file name"),
+            false => format!("`{key}` is not an object and cannot be written to its own file"),
+        };
+        return Err(Error::InvalidInput(message.into()));
+    }
+
+    std::fs::create_dir_all(directory)
+        .inspect_err(|_| log::error!("Could not create the directory `{directory}`"))?;
+
+    let format = args
+        .format
+        .map_or(Format::Dotenv, |format| format.format(args));
+    let required = required(args)?;
+
+    for (key, section) in sections {
+        let vars = args.parse.parse(section)?;
+        check_generated(&vars, &required, args)?;
+        let vars = output_vars(vars, args)?;
+
+        let filename = Path::new(directory).join(format!("{key}.env"));
+        write_vars(&filename.to_string_lossy(), &vars, &format)?;
+    }
+
+    Ok(())
+}
+
+/// Render the variables to the given file, replacing it
+fn write_vars(filename: &str, vars: &[EnvVar], format: &Format) -> Result<(), Error> {
+    let mut writer = open_output(Some(filename))?;
+
+    render::render_to(vars, format, &mut writer)
+        .and_then(|_| Ok(writer.flush()?))
+        .inspect_err(|error| {
+            if let Error::Io(_) = error {
+                log::error!("Could not write to `{filename}`")
+            }
+        })?;
+
+    log::debug!(
+        "Wrote {count} variables to `{filename}`",
+        count = vars.len()
+    );
+    Ok(())
+}
+
+/// Convert the input while it is read, writing each variable as soon as it is generated
+fn stream(args: &Args) -> Result<(), Error> {
+    check_format_options(args)?;
+
+    // the options conflicting with `--stream` on the command line may come from the configuration
+    let parse = &args.parse;
+    let unsupported = [
+        ("root-key", parse.root_key.is_some()),
+        ("target", parse.target.is_some()),
+        ("cast", !parse.cast.is_empty()),
+        ("cast-file", parse.cast_file.is_some()),
+        ("exclude-value", parse.exclude_value.is_some()),
+        ("annotations", parse.annotations),
+    ];
+    if let Some((option, _)) = unsupported.into_iter().find(|(_, given)| *given) {
+        let message = format!("`--stream` cannot apply the `{option}` of the configuration");
+        return Err(Error::InvalidOptions(message));
+    }
+
+    // the format may come from the configuration, the variables are written as `.env` lines
+    if args
+        .format
+        .is_some_and(|format| format != FormatArg::Dotenv)
+    {
+        let message = "`--stream` only writes the `dotenv` format";
+        return Err(Error::InvalidOptions(message.to_owned()));
+    }
+
+    let input = args.input.first().map(String::as_str);
+    let reader = open_input(input)?;
+    let output = args.output.as_deref();
+    let mut writer = CountingWriter::new(open_output(output)?);
+
+    if args.header {
+        writer
+            .write_all(header(&args.input).as_bytes())
+            .inspect_err(|_| log::error!("Could not write to `{}`", output.unwrap_or("STDOUT")))?;
+    }
+
+    let required = required(args)?;
+    let mut present = HashSet::new();
+
+    let mut variables = 0;
+    let streamed = stream::parse_reader(reader, &args.parse.options(), |var| {
+        let var = args.quoting.quote(args.parse.key_case.rename(var));
+        let separator = if variables == 0 { "" } else { "\n" };
+        write!(writer, "{separator}{}", var.render()?)
+            .inspect_err(|_| log::error!("Could not write to `{}`", output.unwrap_or("STDOUT")))?;
+
+        if !is_empty(&var) && required.iter().any(|key| key == var.key()) {
+            present.insert(var.key().to_owned());
+        }
+
+        variables += 1;
+        Ok(())
+    });
+
+    streamed.inspect_err(|error| {
+        if let Error::InvalidInput(_) = error {
+            let input = input.unwrap_or("STDIN");
+            log::error!("`{input}` does not contain valid JSON")
+        }
+    })?;
+
+    writer
+        .flush()
+        .inspect_err(|_| log::error!("Could not write to `{}`", output.unwrap_or("STDOUT")))?;
+    log::debug!("Generated {variables} variables");
+
+    if args.fail_if_empty && variables == 0 {
+        return Err(Error::Validation("No variables were generated".to_owned()));
+    }
+
+    check_required(&required, |key| present.contains(key))?;
+
+    if args.stats {
+        let stats = Stats {
+            variables,
+            skipped: 0,
+            collisions: 0,
+            bytes: writer.bytes,
+        };
+        stats.print(args.log_format);
+    }
+
+    Ok(())
+}
+
+/// Keys of the variables required with `--require` and `--require-file`
+fn required(args: &Args) -> Result<Vec<String>, Error> {
+    let mut required = args.require.clone();
+
+    if let Some(ref filename) = args.require_file {
+        let content = std::fs::read_to_string(filename)
+            .inspect_err(|_| log::error!("Could not read `{filename}`"))?;
+
+        // blank lines and comments are allowed, like in env files
+        required.extend(
+            content
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(str::to_owned),
+        );
+    }
+
+    Ok(required)
+}
+
+/// Fail naming the required variables that are not present, in the order they were required
+fn check_required(required: &[String], present: impl Fn(&str) -> bool) -> Result<(), Error> {
+    let missing = required
+        .iter()
+        .filter(|key| !present(key))
+        .map(|key| format!("`{key}`"))
+        .collect::<Vec<_>>();
+
+    match missing.is_empty() {
+        true => Ok(()),
+        false => Err(Error::Validation(format!(
+            "Required variables are missing or empty: {}",
+            missing.join(", ")
+        ))),
+    }
+}
+
+fn is_empty(var: &EnvVar) -> bool {
+    matches!(var.value(), Value::String(value) if value.is_empty())
+}
+
+/// Parse the inputs in parallel and merge them in order, later inputs override the keys of earlier ones
+fn parse_all(inputs: &[String], parse: &ParseArgs) -> Result<(Vec<EnvVar>, Vec<Warning>), Error> {
+    let parsed = inputs
+        .par_iter()
+        .map(|input| parse.parse_with_warnings(read_json(Some(input), parse)?))
+        .collect::<Vec<_>>();
+
+    // the first error in input order is reported, regardless of which thread failed first
+    let mut merged = EnvMap::new();
+    let mut warnings = Vec::new();
+    for result in parsed {
+        let (vars, input_warnings) = result?;
+        merged.merge(vars, Precedence::Other);
+        warnings.extend(input_warnings);
+    }
+
+    let mut vars = merged.into_vec();
+    vars.sort();
+
+    Ok((vars, warnings))
+}
+
+/// Documents of the concatenated input in order, the first one to fail is reported with its number
+fn documents<'a>(
+    input: Option<&'a str>,
+    args: &'a Args,
+) -> Result<impl Iterator<Item = Result<Value, Error>> + 'a, Error> {
+    let reader = open_input(input)?;
+    let name = input.unwrap_or("STDIN");
+
+    let documents = input::documents(reader)
+        .enumerate()
+        .map(move |(index, json)| {
+            let number = index + 1;
+            let json = json.inspect_err(|_| {
+                log::error!("Document {number} of `{name}` does not contain valid JSON")
+            })?;
+
+            prepare(json, input, &args.parse)
+        });
+
+    Ok(documents)
+}
+
+/// Parse the concatenated documents of the input, later documents override the keys of earlier ones
+fn parse_documents(input: Option<&str>, args: &Args) -> Result<(Vec<EnvVar>, Vec<Warning>), Error> {
+    let mut merged = EnvMap::new();
+    let mut warnings = Vec::new();
+
+    for json in documents(input, args)? {
+        let (vars, document_warnings) = args.parse.parse_with_warnings(json?)?;
+        merged.merge(vars, Precedence::Other);
+        warnings.extend(document_warnings);
+    }
+
+    Ok((merged.into_vec(), warnings))
+}
+
+/// Convert the concatenated documents of the input as they are read, writing each one as a
+/// `# document N` section of a `.env` file
+fn sections(args: &Args) -> Result<(), Error> {
+    let input = args.input.first().map(String::as_str);
+    let output = args.output.as_deref();
+    let mut writer = open_output(output)?;
+
+    for (index, json) in documents(input, args)?.enumerate() {
+        let vars = args.parse.parse(json?)?;
+
+        let separator = if index == 0 { "" } else { "\n\n" };
+        let written = writeln!(writer, "{separator}# document {}", index + 1)
+            .map_err(Error::from)
+            .and_then(|_| render::render_to(&vars, &Format::Dotenv, &mut writer))
+            .and_then(|_| Ok(writer.flush()?));
+
+        written.inspect_err(|error| {
+            if let Error::Io(_) = error {
+                log::error!("Could not write to `{}`", output.unwrap_or("STDOUT"))
+            }
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Writer counting the bytes going through it
+struct CountingWriter<W> {
+    inner: W,
+    bytes: usize,
+}
+
+impl<W: Write> CountingWriter<W> {
+    fn new(inner: W) -> Self {
+        Self { inner, bytes: 0 }
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.bytes += written;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Comment block describing how the output has been generated.
+/// The timestamp honors `SOURCE_DATE_EPOCH` so that reproducible builds get stable files.
+fn header(inputs: &[String]) -> String {
+    let timestamp = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_secs())
+        });
+
+    let flags = std::env::args().skip(1).collect::<Vec<_>>().join(" ");
+
+    format!(
+        "# Generated by json2env {version}, do not edit\n\
+         # Source: {source}\n\
+         # Date: {date}\n\
+         # Command: json2env {flags}\n",
+        version = env!("CARGO_PKG_VERSION"),
+        source = match inputs.is_empty() {
+            true => "STDIN".to_owned(),
+            false => inputs.join(", "),
+        },
+        date = format_timestamp(timestamp),
+    )
+}
+
+/// Format seconds since the Unix epoch as an RFC 3339 UTC date-time, the epoch itself if they are
+/// out of range
+fn format_timestamp(timestamp: u64) -> String {
+    let date = i64::try_from(timestamp)
+        .ok()
+        .and_then(|seconds| DateTime::from_timestamp(seconds, 0))
+        .unwrap_or_default();
+
+    date.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Summary of a conversion, printed to STDERR
+struct Stats {
+    variables: usize,
+    skipped: usize,
+    collisions: usize,
+    bytes: usize,
+}
+
+impl Stats {
+    fn new(vars: &[EnvVar], warnings: &[Warning], bytes: usize) -> Self {
+        let count =
+            |predicate: fn(&Warning) -> bool| warnings.iter().filter(|w| predicate(w)).count();
+
+        Self {
+            variables: vars.len(),
+            skipped: count(|warning| matches!(warning, Warning::EmptyValue { .. })),
+            collisions: count(|warning| matches!(warning, Warning::Collision { .. })),
+            bytes,
+        }
+    }
+
+    fn print(&self, format: LogFormat) {
+        match format {
+            LogFormat::Text => eprintln!(
+                "Variables: {}\nSkipped: {}\nCollisions: {}\nBytes: {}",
+                self.variables, self.skipped, self.collisions, self.bytes
+            ),
+            LogFormat::Json => eprintln!(
+                "{}",
+                serde_json::json!({
+                    "variables": self.variables,
+                    "skipped": self.skipped,
+                    "collisions": self.collisions,
+                    "bytes": self.bytes,
+                })
+            ),
+        }
+    }
+}
+
+/// Regenerate the output every time the input file changes, until interrupted
+fn watch(args: &Args) -> Result<(), Error> {
+    const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+    let debounce = Duration::from_millis(args.debounce);
+    let modified = || {
+        args.input
+            .iter()
+            .map(|input| {
+                std::fs::metadata(input)
+                    .and_then(|meta| meta.modified())
+                    .ok()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // conversion errors are reported and must not stop the watch
+    if let Err(error) = convert(args) {
+        log::error!("{error}");
+    }
+    let mut last: Vec<Option<SystemTime>> = modified();
+
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+
+        let mut current = modified();
+        if current == last {
+            continue;
+        }
+
+        // wait for the file to settle so that a burst of writes triggers a single regeneration
+        loop {
+            std::thread::sleep(debounce);
+
+            let next = modified();
+            if next == current {
+                break;
+            }
+
+            current = next;
+        }
+
+        last = current;
+
+        // like conversion errors, failures of the hook are reported and the watch goes on
+        let converted = convert(args).and_then(|_| match args.on_change {
+            Some(ref command) => run_hook(command),
+            None => Ok(()),
+        });
+        if let Err(error) = converted {
+            log::error!("{error}");
+        }
+    }
+}
+
+/// Run the hook command, given as a program followed by its arguments, failing if it does not
+/// succeed
+fn run_hook(command: &[String]) -> Result<(), Error> {
+    let (program, arguments) = command.split_first().expect("the command is not empty");
+
+    let status = std::process::Command::new(program)
+        .args(arguments)
+        .status()
+        .inspect_err(|_| log::error!("Could not execute `{program}`"))?;
+
+    match status.success() {
+        true => Ok(()),
+        false => {
+            log::error!("The hook `{program}` failed");
+            Err(std::io::Error::other(format!("`{program}` exited with {status}")).into())
+        }
+    }
+}
+
+fn diff(args: DiffArgs) -> Result<(), Error> {
+    let old = read_json(Some(&args.old), &args.parse)?;
+    let new = read_json(Some(&args.new), &args.parse)?;
+
+    let old = args.parse.parse(old)?;
+    let new = args.parse.parse(new)?;
+
+    let old = old.iter().map(Into::into).collect::<Vec<_>>();
+    let new = new.iter().map(Into::into).collect::<Vec<_>>();
+
+    print_diff(&EnvDiff::new(&old, &new), args.mask_values, None)
+}
+
+fn lint(args: LintArgs) -> Result<(), Error> {
+    let json = read_json(args.input.as_deref(), &args.parse)?;
+    let (keys, parsed) = args.parse.parse_with_warnings(json)?;
+
+    let warnings = json2env::lint::lint(&keys);
+    for warning in warnings.iter() {
+        log::warn!("{warning}");
+    }
+
+    // the keys invalid on `--target` have been reported while parsing
+    let invalid = parsed
+        .iter()
+        .filter(|warning| matches!(warning, Warning::InvalidName { .. }))
+        .count();
+
+    match warnings.len() + invalid {
+        0 => Ok(()),
+        1 => Err(Error::Validation("Found 1 issue".to_owned())),
+        count => Err(Error::Validation(format!("Found {count} issues"))),
+    }
+}
+
+/// Convert the JSON files of the input directory in parallel, reporting how many were converted.
+/// A failing file does not stop the others, but the batch fails.
+fn batch(args: BatchArgs, log_format: LogFormat) -> Result<(), Error> {
+    let root = Path::new(&args.input);
+    let files = json_files(root).inspect_err(|_| log::error!("Could not read `{}`", args.input))?;
+
+    let converted = files
+        .par_iter()
+        .map(|file| {
+            let relative = file
+                .strip_prefix(root)
+                .expect("the files are under the root");
+            let output = Path::new(&args.output).join(relative.with_extension("env"));
+
+            let (Some(input), Some(filename)) = (file.to_str(), output.to_str()) else {
+                let message = format!("`{}` is not a valid UTF-8 path", file.display());
+                return Err(Error::InvalidInput(message.into()));
+            };
+
+            let json = read_json(Some(input), &args.parse)?;
+            let vars = args.parse.parse(json)?;
+
+            if let Some(directory) = output.parent() {
+                std::fs::create_dir_all(directory)
+                    .inspect_err(|_| log::error!("Could not create `{}`", directory.display()))?;
+            }
+
+            write_vars(filename, &vars, &Format::Dotenv)?;
+            Ok(vars.len())
+        })
+        .collect::<Vec<_>>();
+
+    let mut variables = 0;
+    let mut failed = 0;
+    for (file, result) in files.iter().zip(converted) {
+        match result {
+            Ok(count) => variables += count,
+            Err(error) => {
+                failed += 1;
+                log::error!("`{}`: {error}", file.display());
+            }
+        }
+    }
+
+    let total = files.len();
+    match log_format {
+        LogFormat::Text => eprintln!(
+            "Converted: {}\nFailed: {failed}\nVariables: {variables}",
+            total - failed
+        ),
+        LogFormat::Json => eprintln!(
+            "{}",
+            serde_json::json!({
+                "converted": total - failed,
+                "failed": failed,
+                "variables": variables,
+            })
+        ),
+    }
+
+    match (failed, args.on_change) {
+        (0, Some(command)) => run_hook(&command),
+        (0, None) => Ok(()),
+        _ => Err(Error::Validation(format!(
+            "{failed} of {total} files could not be converted"
+        ))),
+    }
+}
+
+/// The `.json` and `.jsonc` files under the directory, at any depth, sorted by path
+fn json_files(directory: &Path) -> Result<Vec<std::path::PathBuf>, Error> {
+    let mut files = Vec::new();
+    let mut directories = vec![directory.to_path_buf()];
+
+    while let Some(directory) = directories.pop() {
+        for entry in std::fs::read_dir(&directory)? {
+            let path = entry?.path();
+            let extension = path.extension().and_then(|extension| extension.to_str());
+
+            match extension {
+                _ if path.is_dir() => directories.push(path),
+                Some("json" | "jsonc") => files.push(path),
+                _ => {}
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+fn schema(args: SchemaArgs) -> Result<(), Error> {
+    let json = read_json(args.input.as_deref(), &args.parse)?;
+    let keys = args.parse.parse(json)?;
+
+    let schema = match args.manifest {
+        true => types::describe(&keys),
+        false => types::json_schema(&keys),
+    };
+
+    let mut stdout = std::io::stdout().lock();
+    serde_json::to_writer_pretty(&mut stdout, &schema)?;
+    writeln!(stdout)?;
+    Ok(())
+}
+
+/// Run the command with the generated variables added to its environment, propagating its exit status
+fn exec(args: ExecArgs) -> Result<(), Error> {
+    let json = read_json(args.input.as_deref(), &args.parse)?;
+    let keys = args.parse.parse(json)?;
+
+    let (program, arguments) = args
+        .command
+        .split_first()
+        .expect("clap ensures the command is not empty");
+
+    let mut command = std::process::Command::new(program);
+    command.args(arguments);
+
+    if args.clear_env {
+        command.env_clear();
+    }
+
+    for var in keys.iter() {
+        let (key, value): (String, String) = var.into();
+
+        if args.no_override && !args.clear_env && std::env::var_os(&key).is_some() {
+            continue;
+        }
+
+        command.env(key, value);
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+
+        // only returns if the program could not be executed
+        let error = command.exec();
+        log::error!("Could not execute `{program}`");
+        Err(error.into())
+    }
+
+    #[cfg(not(unix))]
+    {
+        let status = command
+            .status()
+            .inspect_err(|_| log::error!("Could not execute `{program}`"))?;
+
+        std::process::exit(status.code().unwrap_or(1));
+    }
+}
+
+/// Rebuild the JSON document from the variables of an env file
+fn unflatten(args: UnflattenArgs) -> Result<(), Error> {
+    let content = match args.from_env {
+        true => None,
+        false => Some(read_input(args.input.as_deref(), None, false)?),
+    };
+
+    // files generated with `--lossless` are restored exactly, regardless of the other options
+    if let Some(json) = content
+        .as_deref()
+        .map(lossless::restore)
+        .transpose()?
+        .flatten()
+    {
+        let json = serde_json::to_string_pretty(&json)?;
+        return write_output(args.output.as_deref(), &json);
+    }
+
+    let pairs = match content {
+        Some(content) => dotenv::parse(&content),
+        None => environment(),
+    };
+
+    let pairs = match args.prefix {
+        Some(ref prefix) => unflatten::strip_prefix(pairs, prefix),
+        None => pairs,
+    };
+
+    let options = UnflattenOptions::new(args.key_separator, args.infer_types)
+        .index_brackets(args.index_brackets)
+        .index_start(args.array_index_start)
+        .index_width(args.index_width);
+    let json = unflatten::unflatten(&pairs, &options)?;
+    let json = serde_json::to_string_pretty(&json)?;
+
+    write_output(args.output.as_deref(), &json)
+}
+
+/// Variables of the current process environment sorted by key, skipping the ones that are not UTF-8
+fn environment() -> Vec<(String, String)> {
+    let mut environment = std::env::vars_os()
+        .filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?)))
+        .collect::<Vec<_>>();
+
+    environment.sort();
+    environment
+}
+
+/// Read and parse the JSON document from the given file, or from STDIN if not specified
+fn read_json(input: Option<&str>, args: &ParseArgs) -> Result<Value, Error> {
+    let buffer = read_input(input, args.max_input_size, args.lossy_utf8)?;
+
+    let jsonc = input.is_some_and(|input| input.ends_with(".jsonc"));
+    let json = parse_json(buffer, input.unwrap_or("STDIN"), jsonc, args)?;
+
+    prepare(json, input, args)
+}
+
+/// Run the command, given as a program followed by its arguments, and read its output as the JSON
+/// document. Fails with the error output of the command if it does not succeed.
+fn command_json(command: &[String], args: &ParseArgs) -> Result<Value, Error> {
+    let (program, arguments) = command.split_first().expect("the command is not empty");
+
+    let output = std::process::Command::new(program)
+        .args(arguments)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .inspect_err(|_| log::error!("Could not execute `{program}`"))?;
+
+    let errors = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        for line in errors.lines().filter(|line| !line.trim().is_empty()) {
+            log::error!("`{program}`: {line}");
+        }
+
+        return Err(
+            std::io::Error::other(format!("`{program}` exited with {}", output.status)).into(),
+        );
+    }
+
+    for line in errors.lines().filter(|line| !line.trim().is_empty()) {
+        log::debug!("`{program}`: {line}");
+    }
+
+    let buffer = read_text(
+        output.stdout.as_slice(),
+        program,
+        args.max_input_size,
+        args.lossy_utf8,
+    )?;
+    let json = parse_json(buffer, program, false, args)?;
+
+    prepare(json, None, args)
+}
+
+/// Parse the text read from `name` as JSON, or as JSONC if `jsonc` or `--jsonc` is set
+fn parse_json(
+    mut buffer: String,
+    name: &str,
+    jsonc: bool,
+    args: &ParseArgs,
+) -> Result<Value, Error> {
+    if args.jsonc || jsonc {
+        buffer = input::strip_comments(&buffer);
+    }
+
+    input::parse(&buffer).inspect_err(|_| log::error!("`{name}` does not contain valid JSON"))
+}
+
+/// Apply the casts to the document read from `input`, check it against the schema, then nest it
+/// under the root key
+fn prepare(mut json: Value, input: Option<&str>, args: &ParseArgs) -> Result<Value, Error> {
+    if args.cast_file.is_some() || !args.cast.is_empty() {
+        cast::apply(&mut json, &args.casts()?)?;
+    }
+
+    if let Some(ref format) = args.normalize_timestamps {
+        timestamp::normalize(&mut json, format);
+    }
+
+    if let Some(ref schema) = args.schema {
+        schema::validate(&json, input.unwrap_or("STDIN"), schema)?;
+    }
+
+    let root = match args.root_key.as_deref() {
+        None => return Ok(json),
+        Some("auto") => root_key(input)?,
+        Some(name) => name.to_owned(),
+    };
+
+    Ok(Value::Object([(root, json)].into_iter().collect()))
+}
+
+/// Name of the input file without its extension, with the characters other than letters and
+/// digits replaced by `_`
+fn root_key(input: Option<&str>) -> Result<String, Error> {
+    let stem = input
+        .map(Path::new)
+        .and_then(Path::file_stem)
+        .and_then(|stem| stem.to_str())
+        .filter(|stem| !stem.is_empty());
+
+    let Some(stem) = stem else {
+        let message = "The root key cannot be derived from STDIN, it must be given";
+        return Err(Error::InvalidOptions(message.to_owned()));
+    };
+
+    Ok(stem
+        .chars()
+        .map(|char| match char.is_alphanumeric() {
+            true => char,
+            false => '_',
+        })
+        .collect())
+}
+
+/// Key patterns of the redaction file, if any
+fn redaction(filename: Option<&str>) -> Result<Option<Redaction>, Error> {
+    let Some(filename) = filename else {
+        return Ok(None);
+    };
+
+    let content = std::fs::read_to_string(filename)
+        .inspect_err(|_| log::error!("Could not read `{filename}`"))?;
+
+    Ok(Some(Redaction::parse(&content)))
+}
+
+/// Read the whole content of the given file, or of STDIN if not specified.
+/// Fails without reading further if the content is larger than `max_size` bytes.
+fn read_input(input: Option<&str>, max_size: Option<u64>, lossy: bool) -> Result<String, Error> {
+    read_text(
+        open_input(input)?,
+        input.unwrap_or("STDIN"),
+        max_size,
+        lossy,
+    )
+}
+
+/// Read the whole content of the reader of `input` as text, like [`read_input`]
+fn read_text(
+    reader: impl Read,
+    input: &str,
+    max_size: Option<u64>,
+    lossy: bool,
+) -> Result<String, Error> {
+    // one byte past the limit is enough to tell that it has been exceeded
+    let mut reader = reader.take(max_size.map_or(u64::MAX, |size| size + 1));
+    let mut buffer = Vec::new();
+
+    reader
+        .read_to_end(&mut buffer)
+        .inspect_err(|_| log::error!("Could not read `{input}`"))?;
+
+    if let Some(size) = max_size.filter(|&size| buffer.len() as u64 > size) {
+        let message = format!("`{input}` is larger than the limit of {size} bytes");
+        return Err(Error::InvalidInput(message.into()));
+    }
+
+    if lossy {
+        return Ok(input::decode_lossy(buffer));
+    }
+
+    let content = input::decode(buffer).inspect_err(|_| {
+        log::error!("`{input}` is not valid text, `--lossy-utf8` replaces invalid sequences")
+    })?;
+
+    Ok(content)
+}
+
+/// Open the given file for reading, or STDIN if not specified
+fn open_input(input: Option<&str>) -> Result<Box<dyn BufRead>, Error> {
+    match input {
+        None => Ok(Box::new(std::io::stdin().lock())),
+        Some(filename) => {
+            let file = File::open(filename)
+                .inspect_err(|_| log::error!("Could not open file `{filename}`"))?;
+
+            Ok(Box::new(BufReader::new(file)))
+        }
+    }
+}
+
+/// Open the file for writing at its end, creating it if needed
+fn open_append(filename: &str) -> Result<Box<dyn Write>, Error> {
+    let file = File::options()
+        .append(true)
+        .create(true)
+        .open(filename)
+        .inspect_err(|_| log::error!("Could not open file `{filename}`"))?;
+
+    Ok(Box::new(BufWriter::new(file)))
+}
+
+/// Open the given file for writing, or STDOUT if not specified.
+/// Both are buffered: STDOUT would flush every line otherwise, the writer must be flushed when done.
+fn open_output(output: Option<&str>) -> Result<Box<dyn Write>, Error> {
+    match output {
+        None => Ok(Box::new(BufWriter::new(std::io::stdout().lock()))),
+        Some(filename) => {
+            let file = File::create(filename)
+                .inspect_err(|_| log::error!("Could not open file `{filename}`"))?;
+
+            Ok(Box::new(BufWriter::new(file)))
+        }
+    }
+}
+
+/// Write the content to the given file, or to STDOUT if not specified
+fn write_output(output: Option<&str>, content: &str) -> Result<(), Error> {
+    let mut writer = open_output(output)?;
+
+    let output = output.unwrap_or("STDOUT");
+    writer
+        .write_all(content.as_bytes())
+        .and_then(|_| writer.flush())
+        .inspect_err(|_| log::error!("Could not write to `{output}`"))?;
+
+    Ok(())
+}
+
+/// Print the changes to STDOUT, failing with [`Error::Drift`] if there are any
+fn print_diff(
+    diff: &EnvDiff,
+    mask_values: bool,
+    redaction: Option<&Redaction>,
+) -> Result<(), Error> {
+    let mut stdout = std::io::stdout().lock();
+
+    for change in diff.changes() {
+        let redacted = redaction.is_some_and(|redaction| redaction.matches(change.key()));
+        match (mask_values, redacted) {
+            (true, _) => writeln!(stdout, "{}", change.clone().masked())?,
+            (false, true) => writeln!(stdout, "{}", change.clone().masked_with(REDACTED))?,
+            (false, false) => writeln!(stdout, "{change}")?,
+        }
+    }
+
+    stdout.flush()?;
+    match diff.is_empty() {
+        true => Ok(()),
+        false => Err(Error::Drift),
+    }
+}
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "json2env",
+    version,
+    about,
+    args_conflicts_with_subcommands = true
+)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Configuration file, defaults to `json2env.toml` or `.json2envrc` in the working directory
+    #[arg(
+        short,
+        long,
+        global = true,
+        value_name = "FILE",
+        value_hint = ValueHint::FilePath
+    )]
+    config: Option<String>,
+
+    /// Read the input from STDIN even if it is a terminal, instead of showing the help
+    #[arg(long, global = true)]
+    stdin: bool,
+
+    /// Show more detailed messages, can be repeated
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+
+    /// Show fewer messages, can be repeated
+    #[arg(short, long, global = true, action = ArgAction::Count, conflicts_with = "verbose")]
+    quiet: u8,
+
+    /// Format of the messages written to STDERR
+    #[arg(long, global = true, value_name = "FORMAT", default_value = "text")]
+    log_format: LogFormat,
+
+    /// Input file, defaults to STDIN if not specified.
+    /// Can be repeated to merge multiple files, later files override the keys of earlier ones
+    #[arg(short, long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    input: Vec<String>,
+
+    /// Read the input from the output of a command, run without a shell, like
+    /// `--cmd 'aws secretsmanager get-secret-value --secret-id app'`
+    #[arg(
+        long,
+        value_name = "COMMAND",
+        value_parser = command_line,
+        conflicts_with_all = ["input", "stdin", "watch", "stream", "concatenated"]
+    )]
+    // qualified so that clap keeps the words as a single value
+    cmd: Option<std::vec::Vec<String>>,
+
+    /// Read the input from the system clipboard, requires the `clipboard` feature
+    #[arg(
+        long,
+        conflicts_with_all = ["input", "cmd", "stdin", "watch", "stream", "concatenated"]
+    )]
+    from_clipboard: bool,
+
+    /// Copy the variables to the system clipboard instead of writing them, requires the
+    /// `clipboard` feature
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "output", "pretty", "ci", "diff", "update", "lossless", "annotation_comments", "header",
+            "sops_age", "sops_kms", "watch", "stream", "sections", "split_array", "output_dir"
+        ]
+    )]
+    to_clipboard: bool,
+
+    /// Pick the variables to write in a terminal UI, where they can be renamed and edited,
+    /// requires the `interactive` feature
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "lossless", "watch", "stream", "sections", "split_array", "output_dir"
+        ]
+    )]
+    interactive: bool,
+
+    /// Output file, defaults to STDOUT if not specified
+    #[arg(short, long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    output: Option<String>,
+
+    #[command(flatten)]
+    parse: ParseArgs,
+
+    /// Compare the generated variables against an existing env file, exiting with 1 on drift
+    #[arg(
+        long,
+        value_name = "FILE",
+        value_hint = ValueHint::FilePath,
+        conflicts_with = "output"
+    )]
+    diff: Option<String>,
+
+    /// Hide values when printing differences
+    #[arg(long, requires = "diff")]
+    mask_values: bool,
+
+    /// Only write the variables whose value differs from the one in the baseline `.env` file
+    #[arg(
+        long,
+        value_name = "FILE",
+        value_hint = ValueHint::FilePath,
+        conflicts_with_all = ["lossless", "diff", "update", "stream"]
+    )]
+    changed_since: Option<String>,
+
+    /// Replace each value with its digest, to compare the variables of hosts without exposing them
+    #[arg(long, value_name = "ALGORITHM", conflicts_with_all = ["lossless", "stream"])]
+    hash_values: Option<Digest>,
+
+    /// Record the JSON type of each value, in a `KEY__TYPE` variable following it or in a
+    /// `JSON2ENV_TYPES` variable mapping the keys to their types
+    #[arg(
+        long,
+        value_name = "MODE",
+        num_args = 0..=1,
+        default_missing_value = "companions",
+        conflicts_with_all = ["lossless", "stream"]
+    )]
+    emit_types: Option<TypesArg>,
+
+    /// Fail instead of warning when the variables exceed the size limits of Linux, Windows or
+    /// systemd environment files
+    #[arg(long, conflicts_with = "stream")]
+    strict: bool,
+
+    /// Replace with `[REDACTED]` the values of the keys matching a pattern of the file, one per line
+    /// with `*` wildcards, when they are shown in the terminal or in differences
+    #[arg(
+        long,
+        value_name = "FILE",
+        value_hint = ValueHint::FilePath,
+        conflicts_with_all = ["lossless", "stream"]
+    )]
+    redact_file: Option<String>,
+
+    /// Do not warn about the variables that look like secrets when they are written to STDOUT, to
+    /// a file other users can read or as build arguments
+    #[arg(long)]
+    no_secret_warnings: bool,
+
+    /// Include the variables of an existing `.env` file in the output, overridden by the generated
+    /// ones
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath, conflicts_with = "lossless")]
+    base: Option<String>,
+
+    /// Let the variables of the base file override the generated ones
+    #[arg(long, requires = "base")]
+    base_wins: bool,
+
+    /// Include the variables of the current process environment in the output
+    #[arg(long)]
+    inherit_env: bool,
+
+    /// Let the process environment override the generated variables
+    #[arg(long, requires = "inherit_env", conflicts_with = "json_wins")]
+    env_wins: bool,
+
+    /// Let the generated variables override the process environment (default)
+    #[arg(long, requires = "inherit_env")]
+    json_wins: bool,
+
+    /// Precede each variable with a comment recording how to restore the exact original JSON
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "pretty", "group", "inherit_env", "update", "diff", "exclude_value", "substitute"
+        ]
+    )]
+    lossless: bool,
+
+    /// Start the output with comments describing how it has been generated, for the formats
+    /// holding comments
+    #[arg(long, conflicts_with_all = ["pretty", "update", "diff"])]
+    header: bool,
+
+    /// Separate the variables of each top-level key with a comment and a blank line
+    #[arg(short, long, conflicts_with = "pretty")]
+    group: bool,
+
+    /// Align and highlight the output when it is shown in a terminal
+    #[arg(short, long)]
+    pretty: bool,
+
+    /// Format of the output
+    #[arg(
+        short,
+        long,
+        value_name = "FORMAT",
+        conflicts_with_all = ["pretty", "group", "lossless", "ci", "update"]
+    )]
+    format: Option<FormatArg>,
+
+    /// Write all the build arguments in a single line, for `docker build $(json2env ...)`
+    #[arg(long)]
+    single_line: bool,
+
+    /// Values quoted in the `.env` formats, `all` quoting numbers, booleans and null as well
+    #[arg(long, value_name = "VALUES", default_value = "strings")]
+    quoting: Quoting,
+
+    /// Mark the variables of the `export` format readonly, so that scripts cannot change them
+    #[arg(long)]
+    readonly: bool,
+
+    /// Name of the shell function of the `reload` format, `json2env_reload` by default
+    #[arg(long, value_name = "NAME", value_parser = function_name)]
+    reload_name: Option<String>,
+
+    /// Read concatenated JSON documents, like the output of `kubectl get -o json --watch`, the keys
+    /// of later documents override the ones of earlier documents
+    #[arg(long, conflicts_with_all = ["lossless", "split_array", "stream", "jsonc"])]
+    concatenated: bool,
+
+    /// Write each concatenated document as soon as it is read, in a `# document N` section
+    #[arg(
+        long,
+        requires = "concatenated",
+        conflicts_with_all = [
+            "format", "pretty", "group", "header", "ci", "diff", "update", "sops_age", "sops_kms",
+            "base", "inherit_env", "changed_since", "hash_values", "emit_types", "redact_file"
+        ]
+    )]
+    sections: bool,
+
+    /// Leave the annotation keys out of the variables and write them as `# name: value` comments
+    /// of the `.env` file, before the first variable of the annotated object of a single input
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "format", "pretty", "group", "lossless", "ci", "sops_age", "sops_kms", "sections"
+        ]
+    )]
+    annotation_comments: bool,
+
+    /// Precede each variable with a comment holding the JSON pointer of its value, in the formats
+    /// supporting comments
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "pretty", "lossless", "annotation_comments", "stream", "sections", "update", "diff"
+        ]
+    )]
+    annotate: bool,
+
+    /// Convert each object of the root array as a separate document, writing it to the file named
+    /// by the template with `{i}` replaced by its index, like `envs/{i}.env`
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        value_parser = file_template,
+        conflicts_with_all = [
+            "output", "pretty", "group", "lossless", "header", "ci", "diff", "update", "stream",
+            "sops_age", "sops_kms", "base", "inherit_env", "changed_since"
+        ]
+    )]
+    split_array: Option<String>,
+
+    /// Convert each object at the top level as a separate document, written to the directory given
+    /// by `--output-dir` in a file named by its key, like `envs/dev.env`
+    #[arg(long, requires = "output_dir")]
+    split_by_top_level: bool,
+
+    /// Directory of the files written by `--split-by-top-level`, created if needed
+    #[arg(
+        long,
+        value_name = "DIR",
+        value_hint = ValueHint::DirPath,
+        requires = "split_by_top_level",
+        conflicts_with_all = [
+            "output", "pretty", "group", "lossless", "header", "ci", "diff", "update", "stream",
+            "sops_age", "sops_kms", "base", "inherit_env", "changed_since", "split_array",
+            "concatenated"
+        ]
+    )]
+    output_dir: Option<String>,
+
+    /// Write the variables with the native mechanism of a CI system, or of the one detected from
+    /// its environment with `auto`: appended to `$GITHUB_ENV` or `$BASH_ENV` unless an output is
+    /// given, as logging commands for Azure DevOps, as a dotenv report for GitLab CI
+    #[arg(
+        long,
+        value_name = "SYSTEM",
+        conflicts_with_all = ["pretty", "group", "lossless", "header", "diff", "update", "stream"]
+    )]
+    ci: Option<Ci>,
+
+    /// Encrypt the output with `sops` for the age recipient, which must be installed
+    #[arg(
+        long,
+        value_name = "RECIPIENT",
+        conflicts_with_all = ["pretty", "group", "lossless", "header", "format", "ci", "diff", "update", "stream"]
+    )]
+    sops_age: Vec<String>,
+
+    /// Encrypt the output with `sops` for the ARN of the AWS KMS key, which must be installed
+    #[arg(
+        long,
+        value_name = "ARN",
+        conflicts_with_all = ["pretty", "group", "lossless", "header", "format", "ci", "diff", "update", "stream"]
+    )]
+    sops_kms: Vec<String>,
+
+    /// Print a summary of the conversion to STDERR
+    #[arg(long, conflicts_with = "diff")]
+    stats: bool,
+
+    /// Fail if no variables are generated
+    #[arg(long)]
+    fail_if_empty: bool,
+
+    /// Fail if the variable is not generated or is empty, can be repeated
+    #[arg(long, value_name = "KEY")]
+    require: Vec<String>,
+
+    /// Fail if any variable listed in the file, one per line, is not generated or is empty
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    require_file: Option<String>,
+
+    /// Merge the generated variables into an existing env file, preserving unrelated lines
+    #[arg(
+        long,
+        value_name = "FILE",
+        value_hint = ValueHint::FilePath,
+        conflicts_with_all = ["output", "diff"]
+    )]
+    update: Option<String>,
+
+    /// Regenerate the output whenever an input file changes
+    #[arg(short, long, requires_all = ["input", "output"], conflicts_with = "diff")]
+    watch: bool,
+
+    /// Time to wait for the input files to stop changing before regenerating the output
+    #[arg(
+        long,
+        value_name = "MILLISECONDS",
+        default_value_t = 200,
+        requires = "watch"
+    )]
+    debounce: u64,
+
+    /// Command run without a shell after the output is regenerated, like
+    /// `--on-change 'docker compose restart app'`
+    #[arg(long, value_name = "COMMAND", value_parser = command_line, requires = "watch")]
+    // qualified so that clap keeps the words as a single value
+    on_change: Option<std::vec::Vec<String>>,
+
+    /// Write the variables while the input is read, in document order, without loading it in memory
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "lossless", "group", "pretty", "diff", "update", "base", "inherit_env", "watch", "deny_collisions",
+            "max_input_size", "lossy_utf8", "key_by", "schema", "cast", "cast_file", "substitute", "root_key", "jsonc",
+            "annotations", "annotation_comments", "normalize_timestamps", "exclude_value", "target",
+            "format"
+        ]
+    )]
+    stream: bool,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Compare the variables generated by two JSON files, exiting with 1 if they differ
+    Diff(DiffArgs),
+
+    /// Run a command with the generated variables added to its environment
+    Exec(ExecArgs),
+
+    /// Rebuild a JSON document from the variables of an env file
+    Unflatten(UnflattenArgs),
+
+    /// Report suspicious variable names and values, exiting with 5 if any is found
+    Lint(LintArgs),
+
+    /// Convert every JSON file of a directory tree to an env file of the same relative path
+    Batch(BatchArgs),
+
+    /// Print the JSON Schema of the environment generated by a JSON file, to validate it elsewhere
+    Schema(SchemaArgs),
+
+    /// Print the completion script for the given shell
+    Completions {
+        #[arg(value_name = "SHELL")]
+        shell: Shell,
+    },
+}
+
+#[derive(Debug, clap::Args)]
+struct DiffArgs {
+    /// JSON file used as the baseline
+    #[arg(value_name = "OLD", value_hint = ValueHint::FilePath)]
+    old: String,
+
+    /// JSON file compared against the baseline
+    #[arg(value_name = "NEW", value_hint = ValueHint::FilePath)]
+    new: String,
+
+    #[command(flatten)]
+    parse: ParseArgs,
+
+    /// Hide values when printing differences
+    #[arg(long)]
+    mask_values: bool,
+}
+
+#[derive(Debug, clap::Args)]
+struct LintArgs {
+    /// Input file, defaults to STDIN if not specified
+    #[arg(short, long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    input: Option<String>,
+
+    #[command(flatten)]
+    parse: ParseArgs,
+}
+
+#[derive(Debug, clap::Args)]
+struct BatchArgs {
+    /// Directory of the JSON files, `.json` and `.jsonc`, searched recursively
+    #[arg(long = "in", value_name = "DIR", value_hint = ValueHint::DirPath)]
+    input: String,
+
+    /// Directory of the env files, created if needed
+    #[arg(long = "out", value_name = "DIR", value_hint = ValueHint::DirPath)]
+    output: String,
+
+    #[command(flatten)]
+    parse: ParseArgs,
+
+    /// Command run without a shell once every file is converted, like
+    /// `--on-change 'docker compose restart app'`
+    #[arg(long, value_name = "COMMAND", value_parser = command_line)]
+    // qualified so that clap keeps the words as a single value
+    on_change: Option<std::vec::Vec<String>>,
+}
+
+#[derive(Debug, clap::Args)]
+struct SchemaArgs {
+    /// Input file, defaults to STDIN if not specified
+    #[arg(short, long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    input: Option<String>,
+
+    #[command(flatten)]
+    parse: ParseArgs,
+
+    /// Print the name, type and JSON pointer of each variable instead of a JSON Schema
+    #[arg(long)]
+    manifest: bool,
+}
+
+#[derive(Debug, clap::Args)]
+struct ExecArgs {
+    /// Input file, defaults to STDIN if not specified
+    #[arg(short, long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    input: Option<String>,
+
+    #[command(flatten)]
+    parse: ParseArgs,
+
+    /// Keep the value of variables already present in the environment
+    #[arg(long)]
+    no_override: bool,
+
+    /// Do not inherit the current environment, running the command only with the generated variables
+    #[arg(long)]
+    clear_env: bool,
+
+    /// Command to run, followed by its arguments
+    #[arg(
+        last = true,
+        required = true,
+        value_name = "COMMAND",
+        value_hint = ValueHint::CommandWithArguments
+    )]
+    command: Vec<String>,
+}
+
+#[derive(Debug, clap::Args)]
+struct UnflattenArgs {
+    /// Input env file, defaults to STDIN if not specified
+    #[arg(short, long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    input: Option<String>,
+
+    /// Output JSON file, defaults to STDOUT if not specified
+    #[arg(short, long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    output: Option<String>,
+
+    /// Read the variables of the current process environment instead of an env file
+    #[arg(long, conflicts_with = "input")]
+    from_env: bool,
+
+    /// Only use the variables starting with the prefix, removing it from their keys
+    #[arg(short, long, value_name = "STRING")]
+    prefix: Option<String>,
+
+    /// Separator for nested keys
+    #[arg(short = 's', long, value_name = "STRING", default_value = "__")]
+    key_separator: String,
+
+    /// Restore `null`, booleans and numbers instead of keeping every value as a string
+    #[arg(short = 't', long, overrides_with = "no_infer_types")]
+    infer_types: bool,
+
+    /// Keep every value as a string
+    #[arg(long)]
+    no_infer_types: bool,
+
+    /// Only read the keys enclosed in brackets, `KEY__[0]`, as array indices
+    #[arg(long)]
+    index_brackets: bool,
+
+    /// Number of the first element of arrays
+    #[arg(long, value_name = "NUMBER", default_value_t = 0)]
+    array_index_start: usize,
+
+    /// Minimum number of digits of array indices, padded with zeros
+    #[arg(long, value_name = "DIGITS", default_value_t = 0)]
+    index_width: usize,
+}
+
+#[derive(Debug, clap::Args)]
+struct ParseArgs {
+    /// Bundle of options overriding the configuration file but not the other flags: `aspnet` for
+    /// ASP.NET Core, `spring` for Spring Boot, or a preset defined in the configuration file
+    #[arg(long, value_name = "NAME")]
+    preset: Option<String>,
+
+    /// Separator for nested keys
+    #[arg(short = 's', long, value_name = "STRING", default_value = "__")]
+    key_separator: String,
+
+    /// Separators between the first levels of nested keys, like `.` for `DB.HOST__PRIMARY`, the
+    /// deeper levels are separated by `--key-separator`
+    #[arg(long, value_name = "STRINGS", value_delimiter = ',')]
+    key_separator_levels: Vec<String>,
+
+    /// Case of the keys, applied once they are built
+    #[arg(long, value_name = "CASE", default_value = "preserve")]
+    key_case: KeyCase,
+
+    /// Separator for array elements
+    #[arg(short = 'S', long, value_name = "STRING", default_value = ",")]
+    array_separator: String,
+
+    /// Separator for the elements of the array at a JSON pointer, like `/hosts=;`, repeatable
+    #[arg(long, value_name = "POINTER=STRING", value_parser = separator_override)]
+    array_separator_for: Vec<(String, String)>,
+
+    /// Separate array elements in multiple environment variables
+    #[arg(short, long)]
+    enumerate_array: bool,
+
+    /// Nested values split into variables of their own, the others are kept as JSON strings
+    #[arg(long, value_name = "VALUES", conflicts_with = "enumerate_array")]
+    flatten: Option<FlattenArg>,
+
+    /// Enclose array indices in brackets, `KEY__[0]`, to tell them apart from numeric object keys
+    #[arg(long)]
+    index_brackets: bool,
+
+    /// Number the elements of arrays from the given index, like `1` for `KEY__1`, `KEY__2`, ...
+    #[arg(long, value_name = "NUMBER", default_value_t = 0)]
+    array_index_start: usize,
+
+    /// Pad array indices with zeros to the given number of digits, like `KEY__01`, so that sorted
+    /// keys keep the order of the elements
+    #[arg(long, value_name = "DIGITS", default_value_t = 0)]
+    index_width: usize,
+
+    /// Template of the keys of array elements, starting with `{key}` and containing `{i}`, like
+    /// `{key}[{i}]`, replacing the key separator before indices
+    #[arg(long, value_name = "TEMPLATE", conflicts_with = "index_brackets")]
+    index_format: Option<IndexFormat>,
+
+    /// Key the elements of arrays of objects by the value of the given field, like
+    /// `SERVERS__web__HOST` instead of `SERVERS__0__HOST`, if it is distinct in every element
+    #[arg(long, value_name = "FIELD")]
+    key_by: Option<String>,
+
+    /// Also emit the number of elements of enumerated arrays, in `KEY__COUNT` or the given name
+    #[arg(long, value_name = "NAME", num_args = 0..=1, default_missing_value = "COUNT")]
+    array_length: Option<String>,
+
+    /// Fail if more than one value produces the same key
+    #[arg(long)]
+    deny_collisions: bool,
+
+    /// Platform whose naming rules the keys must follow, reporting the invalid ones. Keys differing
+    /// only by their case collide on `windows`
+    #[arg(long, value_name = "PLATFORM")]
+    target: Option<TargetArg>,
+
+    /// Rename the keys invalid on `--target` instead of reporting them, replacing the invalid
+    /// characters with `_`
+    #[arg(long)]
+    sanitize: bool,
+
+    /// Fail if an input is larger than the given number of bytes, before parsing it
+    #[arg(long, value_name = "BYTES")]
+    max_input_size: Option<u64>,
+
+    /// Fail if the input nests more objects and arrays than the given number of levels
+    #[arg(long, value_name = "LEVELS")]
+    max_nesting: Option<usize>,
+
+    /// Replace invalid UTF-8 or UTF-16 sequences in the input instead of failing
+    #[arg(long)]
+    lossy_utf8: bool,
+
+    /// Fail if the input does not match the JSON Schema in the file, reporting every violation
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    schema: Option<String>,
+
+    /// Coerce the value at a JSON pointer to `int`, `float`, `bool` or `string`, like `/port=int`,
+    /// before the schema is checked, nested values cast to `string` are kept as JSON. Can be
+    /// repeated
+    #[arg(long, value_name = "POINTER=TYPE", value_parser = cast_rule)]
+    cast: Vec<(String, Cast)>,
+
+    /// Coerce the values listed in the file, one `POINTER=TYPE` rule per line, before `--cast`
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    cast_file: Option<String>,
+
+    /// Accept `//` and `/* */` comments and trailing commas in the input, like in VS Code settings,
+    /// always for `.jsonc` files
+    #[arg(long)]
+    jsonc: bool,
+
+    /// Leave the keys starting with the annotation prefix out of the variables, they hold metadata
+    #[arg(long)]
+    annotations: bool,
+
+    /// Prefix of the annotation keys
+    #[arg(long, value_name = "PREFIX", default_value = "x-")]
+    annotation_prefix: String,
+
+    /// Nest the variables under a root key, or under the name of the input file with `auto`, like
+    /// `REDIS__*` for `redis.json`
+    #[arg(long, value_name = "NAME")]
+    root_key: Option<String>,
+
+    /// Replace the values equal to a placeholder of the JSON object in the file, like
+    /// `"<VAULT:db_password>"`, with the value it maps to
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    substitute: Option<String>,
+
+    /// Rewrite the date and time strings in RFC 3339, or in the given strftime format like
+    /// `%d/%m/%Y %H:%M`. Times without an offset are read as UTC
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        default_missing_value = "rfc3339",
+        value_parser = timestamp_format
+    )]
+    normalize_timestamps: Option<TimestampFormat>,
+
+    /// Drop the variables whose value matches the regular expression, like `CHANGE_ME|TODO|<.*>`
+    #[arg(long, value_name = "REGEX")]
+    exclude_value: Option<Regex>,
+
+    /// Fail instead of dropping the variables matching `--exclude-value`
+    #[arg(long)]
+    deny_excluded: bool,
+
+    /// Representation of empty objects, and of empty arrays when they are enumerated
+    #[arg(long, value_name = "POLICY", default_value = "skip")]
+    empty_values: EmptyValuesArg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum FormatArg {
+    /// `KEY="value"` lines of a `.env` file
+    Dotenv,
+    /// `--build-arg 'KEY=value'` options of `docker build`
+    BuildArgs,
+    /// `export KEY='value'` shell commands
+    Export,
+    /// Lines of the `$GITHUB_ENV` file of GitHub Actions
+    Github,
+    /// Logging commands of Azure DevOps
+    Azure,
+    /// `.reg` file setting the variables of the Windows user under `HKCU\Environment`
+    Reg,
+    /// `tmux set-environment -g` commands, for the panes of all the sessions
+    Tmux,
+    /// `.env` file quoted for vlucas/phpdotenv, read by Laravel
+    #[value(alias = "laravel")]
+    #[serde(alias = "laravel")]
+    Phpdotenv,
+    /// `export` commands and a shell function running json2env again to refresh them
+    Reload,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum Quoting {
+    /// Strings only, like `PORT=5432`
+    Strings,
+    /// Every value, like `PORT="5432"`
+    All,
+}
+
+impl Quoting {
+    fn quote(self, var: EnvVar) -> EnvVar {
+        match (self, var.value()) {
+            (Quoting::All, Value::Null | Value::Bool(_) | Value::Number(_)) => {
+                let (_, value) = <(String, String)>::from(&var);
+                var.with_value(value)
+            }
+            _ => var,
+        }
+    }
+}
+
+impl Args {
+    /// Take the output options not given on the command line from the preset, then from the
+    /// configuration, like [`ParseArgs::configure`] does for the parsing options
+    fn configure(&mut self, config: &Config, matches: &ArgMatches) -> Result<(), Error> {
+        self.parse.configure(config, matches)?;
+
+        let preset = self.parse.preset(config)?;
+        let is_default = |id: &str| matches.value_source(id) != Some(ValueSource::CommandLine);
+
+        // the output flags conflicting with a format choose one themselves
+        let chosen = ["pretty", "group", "lossless", "ci"]
+            .into_iter()
+            .any(|id| !is_default(id));
+
+        for config in [Some(config), preset.as_ref()].into_iter().flatten() {
+            if let Some(format) = config.format {
+                if is_default("format") && !chosen {
+                    self.format = Some(format);
+                }
+            }
+
+            if let Some(quoting) = config.quoting {
+                if is_default("quoting") {
+                    self.quoting = quoting;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FormatArg {
+    fn format(self, args: &Args) -> Format {
+        match self {
+            FormatArg::Dotenv => Format::Dotenv,
+            FormatArg::BuildArgs => Format::BuildArgs {
+                single_line: args.single_line,
+            },
+            FormatArg::Export => Format::Export {
+                readonly: args.readonly,
+            },
+            FormatArg::Github => Format::GithubEnv,
+            FormatArg::Azure => Format::AzureDevops,
+            FormatArg::Reg => Format::Registry,
+            FormatArg::Tmux => Format::Tmux,
+            FormatArg::Phpdotenv => Format::Phpdotenv,
+            FormatArg::Reload => Format::ReloadFunction {
+                name: (args.reload_name.as_deref())
+                    .unwrap_or("json2env_reload")
+                    .to_owned(),
+                command: reload_command(),
+            },
+        }
+    }
+}
+
+/// Command line of this process writing `export` commands to STDOUT, with the paths made absolute
+/// so that the command can run from any directory
+fn reload_command() -> Vec<String> {
+    let program = std::env::current_exe()
+        .map(|path| path.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| "json2env".to_owned());
+
+    let arguments = std::env::args_os()
+        .skip(1)
+        .map(|arg| arg.to_string_lossy().into_owned());
+
+    [program]
+        .into_iter()
+        .chain(reload_arguments(arguments))
+        .collect()
+}
+
+/// Arguments of [`reload_command`], without the output options and with the values of the path
+/// options, the ones completed as paths, made absolute. `--readonly` is left out as well, the
+/// variables of the previous run could not be replaced otherwise
+fn reload_arguments(mut arguments: impl Iterator<Item = String>) -> Vec<String> {
+    let command = Args::command();
+    let paths = command
+        .get_arguments()
+        .filter(|arg| {
+            matches!(
+                arg.get_value_hint(),
+                ValueHint::FilePath | ValueHint::DirPath | ValueHint::AnyPath
+            )
+        })
+        .flat_map(|arg| {
+            let long = arg.get_long().map(|long| format!("--{long}"));
+            let short = arg.get_short().map(|short| format!("-{short}"));
+            [long, short].into_iter().flatten()
+        })
+        .collect::<HashSet<_>>();
+
+    let mut command = Vec::new();
+    while let Some(arg) = arguments.next() {
+        let (name, value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name, Some(value.to_owned())),
+            _ => (arg.as_str(), None),
+        };
+
+        match name {
+            "-f" | "--format" | "-o" | "--output" | "--reload-name" => {
+                if value.is_none() {
+                    arguments.next();
+                }
+            }
+            "--readonly" => {}
+            name if paths.contains(name) => {
+                if let Some(path) = value.or_else(|| arguments.next()) {
+                    let path = std::path::absolute(&path)
+                        .map_or(path, |path| path.to_string_lossy().into_owned());
+                    command.extend([name.to_owned(), path]);
+                }
+            }
+            _ => command.push(arg),
+        }
+    }
+
+    command.extend(["--format".to_owned(), "export".to_owned()]);
+    command
+}
+
+/// Whether other users can read the file, never where permissions are not the Unix ones
+fn world_readable(filename: &str) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        std::fs::metadata(filename).is_ok_and(|metadata| metadata.permissions().mode() & 0o004 != 0)
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = filename;
+        false
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Digest {
+    Sha256,
+}
+
+impl Digest {
+    /// The variable with its value replaced by `algorithm:hex digest`, of its unquoted value
+    fn hash(self, var: EnvVar) -> EnvVar {
+        let (_, value) = <(String, String)>::from(&var);
+
+        let digest = match self {
+            Digest::Sha256 => sha2::Sha256::digest(value.as_bytes()),
+        };
+        let hex = digest
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+
+        var.with_value(format!("sha256:{hex}"))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum TypesArg {
+    /// A `KEY__TYPE` variable after each variable
+    Companions,
+    /// A single `JSON2ENV_TYPES` variable holding a JSON object
+    Manifest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Ci {
+    /// Detected from the variables set by the CI system
+    Auto,
+    /// GitHub Actions
+    Github,
+    /// GitLab CI/CD
+    Gitlab,
+    /// Azure DevOps Pipelines
+    Azure,
+    /// CircleCI
+    Circleci,
+}
+
+impl Ci {
+    /// The system itself, detecting it if automatic
+    fn resolve(self) -> Result<Self, Error> {
+        if self != Ci::Auto {
+            return Ok(self);
+        }
+
+        let is_set = |name: &str| std::env::var_os(name).is_some_and(|value| !value.is_empty());
+        let detected = [
+            ("GITHUB_ACTIONS", Ci::Github),
+            ("GITLAB_CI", Ci::Gitlab),
+            ("TF_BUILD", Ci::Azure),
+            ("CIRCLECI", Ci::Circleci),
+        ]
+        .into_iter()
+        .find(|(name, _)| is_set(name));
+
+        match detected {
+            Some((name, ci)) => {
+                log::debug!("Detected {ci:?} from `{name}`");
+                Ok(ci)
+            }
+            None => Err(Error::InvalidOptions(
+                "No supported CI system was detected".to_owned(),
+            )),
+        }
+    }
+
+    fn format(self) -> Format {
+        match self {
+            Ci::Github => Format::GithubEnv,
+            Ci::Azure => Format::AzureDevops,
+            Ci::Circleci => Format::Export { readonly: false },
+            Ci::Auto | Ci::Gitlab => Format::Dotenv,
+        }
+    }
+
+    /// Variable naming the file the system reads the variables from, if any
+    fn file(self) -> Option<&'static str> {
+        match self {
+            Ci::Github => Some("GITHUB_ENV"),
+            Ci::Circleci => Some("BASH_ENV"),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Preset {
+    /// ASP.NET Core: `__` separators keeping the case of keys, enumerated arrays, so that
+    /// `ConnectionStrings:Default` becomes `ConnectionStrings__Default`
+    Aspnet,
+    /// Spring Boot relaxed binding: `_` separators and indices, keys in the `spring` case, so that
+    /// `my-app.servers[0].host` becomes `MYAPP_SERVERS_0_HOST`
+    Spring,
+}
+
+impl Preset {
+    fn config(self) -> Config {
+        match self {
+            Preset::Aspnet => Config {
+                key_separator: Some("__".to_owned()),
+                enumerate_array: Some(true),
+                key_case: Some(KeyCase::Aspnet),
+                ..Config::default()
+            },
+            Preset::Spring => Config {
+                key_separator: Some("_".to_owned()),
+                enumerate_array: Some(true),
+                key_case: Some(KeyCase::Spring),
+                ..Config::default()
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum KeyCase {
+    /// As in the document
+    Preserve,
+    /// Uppercase
+    Upper,
+    /// Lowercase
+    Lower,
+    /// Uppercase, with dots replaced by underscores and dashes removed, like Spring Boot expects
+    Spring,
+    /// As in the document, with colons replaced by `__`, like ASP.NET Core expects
+    Aspnet,
+}
+
+impl KeyCase {
+    fn rename(self, var: EnvVar) -> EnvVar {
+        let key = match self {
+            KeyCase::Preserve => return var,
+            KeyCase::Upper => var.key().to_uppercase(),
+            KeyCase::Lower => var.key().to_lowercase(),
+            KeyCase::Spring => var.key().replace('.', "_").replace('-', "").to_uppercase(),
+            KeyCase::Aspnet => var.key().replace(':', "__"),
+        };
+
+        var.with_key(key)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum EmptyValuesArg {
+    /// No variable, with a warning
+    Skip,
+    /// Empty string
+    String,
+    /// `{}` or `[]`, also for joined arrays
+    Literal,
+}
+
+impl From<EmptyValuesArg> for EmptyValues {
+    fn from(empty: EmptyValuesArg) -> Self {
+        match empty {
+            EmptyValuesArg::Skip => EmptyValues::Skip,
+            EmptyValuesArg::String => EmptyValues::EmptyString,
+            EmptyValuesArg::Literal => EmptyValues::Literal,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum FlattenArg {
+    /// Objects, arrays are joined or kept as JSON if they hold nested values
+    Objects,
+    /// Arrays, nested objects are kept as JSON
+    Arrays,
+    /// Objects and arrays, like `--enumerate-array`
+    All,
+}
+
+impl From<FlattenArg> for Flatten {
+    fn from(flatten: FlattenArg) -> Self {
+        match flatten {
+            FlattenArg::Objects => Flatten::Objects,
+            FlattenArg::Arrays => Flatten::Arrays,
+            FlattenArg::All => Flatten::All,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum TargetArg {
+    /// Letters, digits and `_`, not starting with a digit
+    Posix,
+    /// Also `(` and `)`, like `ProgramFiles(x86)`, regardless of the case
+    Windows,
+}
+
+impl From<TargetArg> for Target {
+    fn from(target: TargetArg) -> Self {
+        match target {
+            TargetArg::Posix => Target::Posix,
+            TargetArg::Windows => Target::Windows,
+        }
+    }
+}
+
+impl ParseArgs {
+    /// Take the values not given on the command line from the preset, then from the configuration.
+    /// Presets of the configuration file take precedence over the built-in ones.
+    fn configure(&mut self, config: &Config, matches: &ArgMatches) -> Result<(), Error> {
+        self.apply(config, matches)?;
+
+        if let Some(preset) = self.preset(config)? {
+            self.apply(&preset, matches)?;
+        }
+
+        // the options they depend on may come from the configuration
+        if self.sanitize && self.target.is_none() {
+            let message = "`--sanitize` requires `--target`";
+            return Err(Error::InvalidOptions(message.to_owned()));
+        }
+
+        if self.deny_excluded && self.exclude_value.is_none() {
+            let message = "`--deny-excluded` requires `--exclude-value`";
+            return Err(Error::InvalidOptions(message.to_owned()));
+        }
+
+        Ok(())
+    }
+
+    /// Options of the selected preset, failing if it is not defined
+    fn preset(&self, config: &Config) -> Result<Option<Config>, Error> {
+        let Some(ref name) = self.preset else {
+            return Ok(None);
+        };
+
+        let preset = match config.presets.get(name) {
+            Some(preset) => preset.clone(),
+            None => Preset::from_str(name, true)
+                .map(Preset::config)
+                .map_err(|_| {
+                    let builtin = Preset::value_variants()
+                        .iter()
+                        .filter_map(|preset| preset.to_possible_value())
+                        .map(|value| format!("`{}`", value.get_name()))
+                        .collect::<Vec<_>>();
+
+                    Error::InvalidOptions(format!(
+                        "`{name}` is not a preset of the configuration file nor one of {}",
+                        builtin.join(", ")
+                    ))
+                })?,
+        };
+
+        Ok(Some(preset))
+    }
+
+    /// Take the values not given on the command line from the configuration, failing if they are
+    /// not valid
+    fn apply(&mut self, config: &Config, matches: &ArgMatches) -> Result<(), Error> {
+        let is_default = |id: &str| matches.value_source(id) != Some(ValueSource::CommandLine);
+
+        if let Some(ref separator) = config.key_separator {
+            if is_default("key_separator") {
+                self.key_separator = separator.clone();
+            }
+        }
+
+        if let Some(ref separator) = config.array_separator {
+            if is_default("array_separator") {
+                self.array_separator = separator.clone();
+            }
+        }
+
+        if let Some(enumerate) = config.enumerate_array {
+            if is_default("enumerate_array") {
+                self.enumerate_array = enumerate;
+            }
+        }
+
+        if let Some(case) = config.key_case {
+            if is_default("key_case") {
+                self.key_case = case;
+            }
+        }
+
+        if let Some(ref name) = config.root_key {
+            if is_default("root_key") {
+                self.root_key = Some(name.clone());
+            }
+        }
+
+        if let Some(target) = config.target {
+            if is_default("target") {
+                self.target = Some(target);
+            }
+        }
+
+        if let Some(sanitize) = config.sanitize {
+            if is_default("sanitize") {
+                self.sanitize = sanitize;
+            }
+        }
+
+        if let Some(ref rules) = config.cast {
+            if is_default("cast") {
+                self.cast = rules
+                    .iter()
+                    .map(|rule| cast_rule(rule))
+                    .collect::<Result<_, _>>()
+                    .map_err(|error| Error::InvalidOptions(format!("`cast`: {error}")))?;
+            }
+        }
+
+        if let Some(ref filename) = config.cast_file {
+            if is_default("cast_file") {
+                self.cast_file = Some(filename.clone());
+            }
+        }
+
+        if let Some(ref pattern) = config.exclude_value {
+            if is_default("exclude_value") {
+                let pattern = Regex::new(pattern)
+                    .map_err(|error| Error::InvalidOptions(format!("`exclude-value`: {error}")))?;
+                self.exclude_value = Some(pattern);
+            }
+        }
+
+        if let Some(deny) = config.deny_excluded {
+            if is_default("deny_excluded") {
+                self.deny_excluded = deny;
+            }
+        }
+
+        if let Some(annotations) = config.annotations {
+            if is_default("annotations") {
+                self.annotations = annotations;
+            }
+        }
+
+        if let Some(ref prefix) = config.annotation_prefix {
+            if is_default("annotation_prefix") {
+                self.annotation_prefix = prefix.clone();
+            }
+        }
+
+        Ok(())
+    }
+
+    fn options(&self) -> ParseOptions {
+        let builder = ParseOptions::builder()
+            .key_separator(&self.key_separator)
+            .key_separator_levels(self.key_separator_levels.iter())
+            .array_separator(&self.array_separator)
+            .enumerate_array(self.enumerate_array)
+            .index_brackets(self.index_brackets)
+            .index_start(self.array_index_start)
+            .index_width(self.index_width)
+            .empty_values(self.empty_values.into());
+
+        let builder = match self.index_format {
+            Some(ref format) => builder.index_format(format.clone()),
+            None => builder,
+        };
+        let builder = match self.flatten {
+            Some(flatten) => builder.flatten(flatten.into()),
+            None => builder,
+        };
+        let builder = match self.key_by {
+            Some(ref field) => builder.key_by(field),
+            None => builder,
+        };
+        let builder = match self.array_length {
+            Some(ref name) => builder.array_length(name),
+            None => builder,
+        };
+        let builder = self
+            .array_separator_for
+            .iter()
+            .fold(builder, |builder, (pointer, separator)| {
+                builder.array_separator_for(pointer, separator)
+            });
+
+        match self.max_nesting {
+            Some(depth) => builder.max_depth(depth).build(),
+            None => builder.build(),
+        }
+    }
+
+    /// Casts of the rules file followed by the ones given on the command line
+    fn casts(&self) -> Result<Vec<(String, Cast)>, Error> {
+        let mut casts = Vec::new();
+
+        if let Some(ref filename) = self.cast_file {
+            let content = std::fs::read_to_string(filename)
+                .inspect_err(|_| log::error!("Could not read `{filename}`"))?;
+
+            for (number, line) in content.lines().enumerate() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+
+                let rule = cast_rule(line).map_err(|error| {
+                    Error::InvalidOptions(format!("`{filename}` line {}: {error}", number + 1))
+                })?;
+                casts.push(rule);
+            }
+        }
+
+        casts.extend(self.cast.iter().cloned());
+        Ok(casts)
+    }
+
+    fn parser(&self) -> JsonParser {
+        let parser = JsonParser::new(self.options());
+
+        match self.key_case {
+            KeyCase::Preserve => parser,
+            case => parser.with_transform(move |var| Some(case.rename(var))),
+        }
+    }
+
+    /// Parse the document, logging the warnings
+    fn parse(&self, json: Value) -> Result<Vec<EnvVar>, Error> {
+        self.parse_with_warnings(json).map(|(vars, _)| vars)
+    }
+
+    /// Parse the document, logging the warnings before returning them
+    fn parse_with_warnings(&self, mut json: Value) -> Result<(Vec<EnvVar>, Vec<Warning>), Error> {
+        if self.annotations {
+            annotation::strip(&mut json, &self.annotation_prefix);
+        }
+
+        let (mut vars, mut warnings) = self.parser().parse_owned_with_warnings(json)?;
+
+        if let Some(target) = self.target.map(Target::from) {
+            match self.sanitize {
+                true => vars = sanitize(vars, target),
+                false => warnings.extend(target.validate(&vars)),
+            }
+
+            // the keys are compared with the rules of the platform
+            warnings.retain(|warning| !matches!(warning, Warning::Collision { .. }));
+            warnings.extend(target.collisions(&vars));
+        }
+
+        self.report(&vars, &warnings)?;
+
+        if let Some(ref filename) = self.substitute {
+            let content = std::fs::read_to_string(filename)
+                .inspect_err(|_| log::error!("Could not read `{filename}`"))?;
+
+            let mapping = input::parse(&content)
+                .inspect_err(|_| log::error!("`{filename}` does not contain valid JSON"))?;
+            let Value::Object(mapping) = mapping else {
+                let message = format!("`{filename}` must contain an object of placeholders");
+                return Err(Error::InvalidInput(message.into()));
+            };
+
+            substitute::substitute(&mut vars, &mapping)?;
+        }
+
+        if let Some(ref pattern) = self.exclude_value {
+            vars = self.exclude(vars, pattern)?;
+        }
+
+        Ok((vars, warnings))
+    }
+
+    /// The variables whose unquoted value does not match the pattern, failing on the first one that
+    /// does if they are denied
+    fn exclude(&self, vars: Vec<EnvVar>, pattern: &Regex) -> Result<Vec<EnvVar>, Error> {
+        let mut kept = Vec::with_capacity(vars.len());
+
+        for var in vars {
+            let (_, value) = <(String, String)>::from(&var);
+            if !pattern.is_match(&value) {
+                kept.push(var);
+                continue;
+            }
+
+            let message = format!("The value of `{}` matches `{pattern}`", var.key());
+            match self.deny_excluded {
+                true => return Err(Error::Validation(message)),
+                false => log::warn!("{message}, it is dropped"),
+            }
+        }
+
+        Ok(kept)
+    }
+
+    /// Log the warnings, failing on collisions if they are denied
+    fn report(&self, vars: &[EnvVar], warnings: &[Warning]) -> Result<(), Error> {
+        for warning in warnings.iter() {
+            match warning {
+                Warning::Collision { key, .. } if self.deny_collisions => {
+                    return Err(Error::Collision { key: key.clone() })
+                }
+                warning => log::warn!("{warning}"),
+            }
+        }
+
+        log::debug!("Generated {count} variables", count = vars.len());
+        Ok(())
+    }
+}
+
+/// Rename the variables whose key is invalid on the target, logging their new key
+fn sanitize(vars: Vec<EnvVar>, target: Target) -> Vec<EnvVar> {
+    vars.into_iter()
+        .map(|var| match target.sanitize(var.key()) {
+            Cow::Borrowed(_) => var,
+            Cow::Owned(key) => {
+                log::warn!(
+                    "`{}` is not a valid name on {target}, it is renamed `{key}`",
+                    var.key()
+                );
+                var.with_key(key)
+            }
+        })
+        .collect()
+}
+
+/// Parse a `POINTER=TYPE` cast rule
+fn cast_rule(arg: &str) -> Result<(String, Cast), String> {
+    match arg.rsplit_once('=') {
+        Some((pointer, cast)) if pointer.is_empty() || pointer.starts_with('/') => {
+            let cast = cast
+                .trim()
+                .parse()
+                .map_err(|error: Error| error.to_string())?;
+            Ok((pointer.trim().to_owned(), cast))
+        }
+        _ => Err(format!(
+            "`{arg}` is not a JSON pointer starting with `/`, an `=` and a type"
+        )),
+    }
+}
+
+/// Parse a file name template, which must contain `{i}` to tell the files apart
+fn file_template(arg: &str) -> Result<String, String> {
+    match arg.contains("{i}") {
+        true => Ok(arg.to_owned()),
+        false => Err(format!("`{arg}` does not contain `{{i}}`")),
+    }
+}
+
+/// Parse `rfc3339` or a strftime format of the normalized timestamps
+fn timestamp_format(arg: &str) -> Result<TimestampFormat, String> {
+    match arg {
+        "rfc3339" => Ok(TimestampFormat::Rfc3339),
+        layout => TimestampFormat::strftime(layout).map_err(|error| error.to_string()),
+    }
+}
+
+/// Check that the name is a portable shell function name, letters, digits and `_`
+fn function_name(arg: &str) -> Result<String, String> {
+    let valid = arg.starts_with(|char: char| char.is_ascii_alphabetic() || char == '_')
+        && arg
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || char == '_');
+
+    match valid {
+        true => Ok(arg.to_owned()),
+        false => Err(format!("`{arg}` is not a valid shell function name")),
+    }
+}
+
+/// Split a command line in words at whitespace, keeping the `'` and `"` quoted parts whole.
+/// Outside of single quotes, `\` escapes the next character.
+fn command_line(arg: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    let mut chars = arg.chars();
+
+    while let Some(char) = chars.next() {
+        match (quote, char) {
+            (Some('\''), '\'') | (Some('"'), '"') => quote = None,
+            (Some('"') | None, '\\') => {
+                let escaped = chars.next().ok_or("The command ends with `\\`")?;
+                word.get_or_insert_default().push(escaped);
+            }
+            (Some(_), char) => word.get_or_insert_default().push(char),
+            (None, '\'' | '"') => {
+                quote = Some(char);
+                word.get_or_insert_default();
+            }
+            (None, char) if char.is_whitespace() => words.extend(word.take()),
+            (None, char) => word.get_or_insert_default().push(char),
+        }
+    }
+
+    if let Some(quote) = quote {
+        return Err(format!("The command has an unterminated `{quote}` quote"));
+    }
+
+    words.extend(word);
+    match words.is_empty() {
+        true => Err("The command is empty".to_owned()),
+        false => Ok(words),
+    }
+}
+
+/// Parse a `POINTER=STRING` separator override, the separator may contain `=`
+fn separator_override(arg: &str) -> Result<(String, String), String> {
+    match arg.split_once('=') {
+        Some((pointer, separator)) if pointer.is_empty() || pointer.starts_with('/') => {
+            Ok((pointer.to_owned(), separator.to_owned()))
+        }
+        _ => Err(format!(
+            "`{arg}` is not a JSON pointer starting with `/`, an `=` and a separator"
+        )),
+    }
+}
@@ -0,0 +1,211 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde_json::Value;
+
+use crate::{diff::EnvDiff, merge::Precedence, EnvVar};
+
+/// Variables with unique keys, kept in insertion order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnvMap {
+    vars: Vec<EnvVar>,
+    /// Position of each key in `vars`
+    index: HashMap<String, usize>,
+}
+
+impl EnvMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.vars.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vars.is_empty()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.index.get(key).map(|&position| &self.vars[position].1)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.index.contains_key(key)
+    }
+
+    /// Set the value of the variable, keeping its position if the key is already present.
+    ///
+    /// Returns the replaced value, if any.
+    pub fn insert(&mut self, var: EnvVar) -> Option<Value> {
+        match self.index.get(&var.0) {
+            Some(&position) => Some(std::mem::replace(&mut self.vars[position].1, var.1)),
+            None => {
+                self.index.insert(var.0.clone(), self.vars.len());
+                self.vars.push(var);
+                None
+            }
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &EnvVar> {
+        self.vars.iter()
+    }
+
+    /// Add the other variables, the `precedence` decides the value of keys defined by both.
+    ///
+    /// Like [`crate::merge::merge`], variables of `self` come first followed by the new keys.
+    pub fn merge(&mut self, other: impl IntoIterator<Item = EnvVar>, precedence: Precedence) {
+        for var in other {
+            if precedence == Precedence::Other || !self.contains_key(&var.0) {
+                self.insert(var);
+            }
+        }
+    }
+
+    /// Changes needed to go from `self` to `other`
+    pub fn diff(&self, other: &EnvMap) -> EnvDiff {
+        EnvDiff::new(&self.pairs(), &other.pairs())
+    }
+
+    /// Keep only the variables for which the predicate returns `true`
+    pub fn retain(&mut self, mut predicate: impl FnMut(&EnvVar) -> bool) {
+        self.vars.retain(|var| predicate(var));
+        self.reindex();
+    }
+
+    pub fn to_vec(&self) -> Vec<EnvVar> {
+        self.vars.clone()
+    }
+
+    pub fn into_vec(self) -> Vec<EnvVar> {
+        self.vars
+    }
+
+    fn pairs(&self) -> Vec<(String, String)> {
+        self.vars.iter().map(Into::into).collect()
+    }
+
+    fn reindex(&mut self) {
+        self.index = self
+            .vars
+            .iter()
+            .enumerate()
+            .map(|(position, var)| (var.0.clone(), position))
+            .collect();
+    }
+}
+
+/// Later definitions of a key override the earlier ones, at the position of the first
+impl FromIterator<EnvVar> for EnvMap {
+    fn from_iter<T: IntoIterator<Item = EnvVar>>(iter: T) -> Self {
+        let mut map = Self::new();
+        for var in iter {
+            map.insert(var);
+        }
+
+        map
+    }
+}
+
+impl From<Vec<EnvVar>> for EnvMap {
+    fn from(vars: Vec<EnvVar>) -> Self {
+        vars.into_iter().collect()
+    }
+}
+
+/// Unquoted values sorted by key
+impl From<EnvMap> for BTreeMap<String, String> {
+    fn from(map: EnvMap) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+impl From<EnvMap> for HashMap<String, String> {
+    fn from(map: EnvMap) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+impl IntoIterator for EnvMap {
+    type Item = EnvVar;
+    type IntoIter = std::vec::IntoIter<EnvVar>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.vars.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::EnvMap;
+    use crate::{diff::Change, merge::Precedence, EnvVar};
+
+    #[test]
+    fn from_vec_should_keep_last_value_at_first_position() {
+        // ARRANGE
+        let vars = vec![
+            EnvVar::new("A", 1),
+            EnvVar::new("B", 2),
+            EnvVar::new("A", 3),
+        ];
+
+        // ACT
+        let map = EnvMap::from(vars);
+
+        // ASSERT
+        assert_eq!(map.to_vec(), vec![EnvVar::new("A", 3), EnvVar::new("B", 2)])
+    }
+
+    #[test]
+    fn merge_should_respect_precedence() {
+        // ARRANGE
+        let map = EnvMap::from(vec![EnvVar::new("A", 1)]);
+        let other = || vec![EnvVar::new("A", 2), EnvVar::new("B", 3)];
+
+        // ACT
+        let mut kept = map.clone();
+        kept.merge(other(), Precedence::Generated);
+        let mut overridden = map;
+        overridden.merge(other(), Precedence::Other);
+
+        // ASSERT
+        assert_eq!(kept.get("A"), Some(&json!(1)));
+        assert_eq!(overridden.get("A"), Some(&json!(2)));
+        assert_eq!(kept.get("B"), Some(&json!(3)))
+    }
+
+    #[test]
+    fn retain_should_update_lookups() {
+        // ARRANGE
+        let mut map = EnvMap::from(vec![EnvVar::new("A", 1), EnvVar::new("B", 2)]);
+
+        // ACT
+        map.retain(|var| var.key() != "A");
+
+        // ASSERT
+        assert_eq!(map.get("A"), None);
+        assert_eq!(map.get("B"), Some(&json!(2)))
+    }
+
+    #[test]
+    fn diff_should_report_changes_to_other() {
+        // ARRANGE
+        let old = EnvMap::from(vec![EnvVar::new("A", 1)]);
+        let new = EnvMap::from(vec![EnvVar::new("A", 2)]);
+
+        // ACT
+        let diff = old.diff(&new);
+
+        // ASSERT
+        assert_eq!(
+            diff.changes(),
+            [Change::Changed {
+                key: "A".to_owned(),
+                old: "1".to_owned(),
+                new: "2".to_owned()
+            }]
+        )
+    }
+}
@@ -0,0 +1,95 @@
+use serde_json::Value;
+
+use crate::{map::EnvMap, EnvVar};
+
+/// Side keeping its value when a key is defined by both sets of variables
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Precedence {
+    /// Generated variables override the others
+    #[default]
+    Generated,
+    /// Other variables override the generated ones
+    Other,
+}
+
+/// Merge other string variables, like those of the process environment, with the generated ones.
+///
+/// Generated variables keep their order and come first, followed by the other keys they did not define.
+/// Like in `.env` files, the last definition of a key defined more than once is the effective one.
+pub fn merge(
+    vars: Vec<EnvVar>,
+    other: impl IntoIterator<Item = (String, String)>,
+    precedence: Precedence,
+) -> Vec<EnvVar> {
+    let other = other
+        .into_iter()
+        .map(|(key, value)| EnvVar::new(key, Value::String(value)))
+        .collect::<EnvMap>();
+
+    let mut merged = EnvMap::from(vars);
+    merged.merge(other, precedence);
+    merged.into_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{merge, Precedence};
+    use crate::EnvVar;
+
+    fn other() -> Vec<(String, String)> {
+        vec![
+            ("A".to_owned(), "env".to_owned()),
+            ("B".to_owned(), "env".to_owned()),
+        ]
+    }
+
+    #[test]
+    fn merge_should_keep_generated_values_by_default() {
+        // ARRANGE
+        let vars = vec![EnvVar::new("A".to_owned(), json!(1))];
+
+        // ACT
+        let result = merge(vars, other(), Precedence::default());
+
+        // ASSERT
+        assert_eq!(
+            result,
+            vec![
+                EnvVar::new("A".to_owned(), json!(1)),
+                EnvVar::new("B".to_owned(), json!("env"))
+            ]
+        )
+    }
+
+    #[test]
+    fn merge_should_keep_the_last_definition_of_other_keys() {
+        // ARRANGE
+        let other = [("B", "first"), ("B", "last")].map(|(key, value)| (key.into(), value.into()));
+
+        // ACT
+        let result = merge(vec![EnvVar::new("A", 1)], other, Precedence::default());
+
+        // ASSERT
+        assert_eq!(result, vec![EnvVar::new("A", 1), EnvVar::new("B", "last")])
+    }
+
+    #[test]
+    fn merge_should_override_generated_values_when_other_wins() {
+        // ARRANGE
+        let vars = vec![EnvVar::new("A".to_owned(), json!(1))];
+
+        // ACT
+        let result = merge(vars, other(), Precedence::Other);
+
+        // ASSERT
+        assert_eq!(
+            result,
+            vec![
+                EnvVar::new("A".to_owned(), json!("env")),
+                EnvVar::new("B".to_owned(), json!("env"))
+            ]
+        )
+    }
+}
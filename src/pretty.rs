@@ -0,0 +1,95 @@
+use serde_json::Value;
+
+use crate::EnvVar;
+
+const RESET: &str = "\x1b[0m";
+const KEY: &str = "\x1b[1;36m";
+const STRING: &str = "\x1b[32m";
+const NUMBER: &str = "\x1b[33m";
+const BOOL: &str = "\x1b[35m";
+const DIM: &str = "\x1b[2m";
+
+/// Render the variables for interactive reading: `=` columns are aligned and each value is
+/// annotated with its JSON type, highlighting keys and values with ANSI colors if `color` is set.
+pub fn pretty(vars: &[EnvVar], color: bool) -> String {
+    let rendered = vars
+        .iter()
+        .map(|var| {
+            let value = var.to_string()[var.0.len() + 1..].to_owned();
+            (var, value)
+        })
+        .collect::<Vec<_>>();
+
+    let key_width = vars
+        .iter()
+        .map(|var| var.0.chars().count())
+        .max()
+        .unwrap_or(0);
+    let value_width = rendered
+        .iter()
+        .map(|(_, value)| value.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let paint = |style: &str, text: &str| match color {
+        true => format!("{style}{text}{RESET}"),
+        false => text.to_owned(),
+    };
+
+    let mut lines = Vec::with_capacity(vars.len());
+    for (var, value) in rendered {
+        let (style, kind) = match var.1 {
+            Value::Null => (DIM, "null"),
+            Value::Bool(_) => (BOOL, "bool"),
+            Value::Number(_) => (NUMBER, "number"),
+            _ => (STRING, "string"),
+        };
+
+        let key_padding = " ".repeat(key_width - var.0.chars().count());
+        let value_padding = " ".repeat(value_width - value.chars().count());
+
+        lines.push(format!(
+            "{key}{key_padding} = {value}{value_padding}  {kind}",
+            key = paint(KEY, &var.0),
+            value = paint(style, &value),
+            kind = paint(DIM, &format!("# {kind}")),
+        ));
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::pretty;
+    use crate::EnvVar;
+
+    #[test]
+    fn pretty_should_align_values_and_annotate_types() {
+        // ARRANGE
+        let vars = vec![
+            EnvVar::new("A".to_owned(), json!("x")),
+            EnvVar::new("LONG".to_owned(), json!(10)),
+        ];
+
+        // ACT
+        let result = pretty(&vars, false);
+
+        // ASSERT
+        assert_eq!(result, "A    = \"x\"  # string\nLONG = 10   # number")
+    }
+
+    #[test]
+    fn pretty_should_color_keys_when_enabled() {
+        // ARRANGE
+        let vars = vec![EnvVar::new("A".to_owned(), json!(true))];
+
+        // ACT
+        let result = pretty(&vars, true);
+
+        // ASSERT
+        assert!(result.starts_with("\x1b[1;36mA\x1b[0m = \x1b[35mtrue\x1b[0m"))
+    }
+}
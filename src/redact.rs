@@ -0,0 +1,104 @@
+use crate::EnvVar;
+
+/// Replacement of the redacted values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Key patterns whose values are hidden, with `*` matching any run of characters, in any case
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Redaction {
+    patterns: Vec<String>,
+}
+
+impl Redaction {
+    pub fn new(patterns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            patterns: patterns
+                .into_iter()
+                .map(|pattern| pattern.into().to_uppercase())
+                .collect(),
+        }
+    }
+
+    /// Patterns of a file, one per line, ignoring blank lines and `#` comments
+    pub fn parse(content: &str) -> Self {
+        Self::new(
+            content
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#')),
+        )
+    }
+
+    pub fn matches(&self, key: &str) -> bool {
+        let key = key.to_uppercase();
+        self.patterns.iter().any(|pattern| glob(pattern, &key))
+    }
+
+    /// The variables with the values of the matching keys replaced by [`REDACTED`]
+    pub fn redact(&self, vars: &[EnvVar]) -> Vec<EnvVar> {
+        vars.iter()
+            .map(|var| match self.matches(var.key()) {
+                true => var.clone().with_value(REDACTED),
+                false => var.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Whether the text matches the pattern, where `*` matches any run of characters
+fn glob(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts = parts.collect::<Vec<_>>();
+    let Some((last, middle)) = parts.split_last() else {
+        // no `*` in the pattern
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(start) => rest = &rest[start + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Redaction, REDACTED};
+    use crate::EnvVar;
+
+    #[test]
+    fn redact_should_hide_the_values_of_matching_keys() {
+        // ARRANGE
+        let redaction = Redaction::parse("# secrets\n*password*\n\nAPI_*_KEY\nTOKEN\n");
+        let vars = vec![
+            EnvVar::new("DB__PASSWORD", "s3cret"),
+            EnvVar::new("API_STRIPE_KEY", "sk"),
+            EnvVar::new("API_KEY_ID", "id"),
+            EnvVar::new("token", "t"),
+            EnvVar::new("TOKENS", 2),
+        ];
+
+        // ACT
+        let result = redaction.redact(&vars);
+
+        // ASSERT
+        assert_eq!(
+            result,
+            vec![
+                EnvVar::new("DB__PASSWORD", REDACTED),
+                EnvVar::new("API_STRIPE_KEY", REDACTED),
+                EnvVar::new("API_KEY_ID", "id"),
+                EnvVar::new("token", REDACTED),
+                EnvVar::new("TOKENS", 2),
+            ]
+        )
+    }
+}
@@ -0,0 +1,856 @@
+use std::io::Write;
+
+use serde_json::Value;
+
+use crate::{target::Target, EnvVar, Error};
+
+/// Output format of the variables, implemented by downstream crates to plug their own
+pub trait Renderer {
+    fn render(&self, vars: &[EnvVar], writer: &mut dyn Write) -> Result<(), Error>;
+}
+
+/// Built-in output formats
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Format {
+    /// See [`Dotenv`]
+    #[default]
+    Dotenv,
+    /// See [`Pretty`]
+    #[cfg(feature = "pretty")]
+    Pretty { color: bool },
+    /// See [`Grouped`]
+    #[cfg(feature = "grouped")]
+    Grouped { key_separator: String },
+    /// See [`GithubEnv`]
+    GithubEnv,
+    /// See [`AzureDevops`]
+    AzureDevops,
+    /// See [`Export`]
+    Export { readonly: bool },
+    /// See [`BuildArgs`]
+    BuildArgs { single_line: bool },
+    /// See [`Registry`]
+    Registry,
+    /// See [`Tmux`]
+    Tmux,
+    /// See [`Phpdotenv`]
+    Phpdotenv,
+    /// See [`ReloadFunction`]
+    ReloadFunction { name: String, command: Vec<String> },
+}
+
+impl Format {
+    pub fn renderer(&self) -> Box<dyn Renderer> {
+        match self {
+            Format::Dotenv => Box::new(Dotenv),
+            #[cfg(feature = "pretty")]
+            Format::Pretty { color } => Box::new(Pretty::new(*color)),
+            #[cfg(feature = "grouped")]
+            Format::Grouped { key_separator } => Box::new(Grouped::new(key_separator)),
+            Format::GithubEnv => Box::new(GithubEnv),
+            Format::AzureDevops => Box::new(AzureDevops),
+            Format::Export { readonly } => Box::new(Export::new(*readonly)),
+            Format::BuildArgs { single_line } => Box::new(BuildArgs::new(*single_line)),
+            Format::Registry => Box::new(Registry),
+            Format::Tmux => Box::new(Tmux),
+            Format::Phpdotenv => Box::new(Phpdotenv),
+            Format::ReloadFunction { name, command } => {
+                Box::new(ReloadFunction::new(name, command.clone()))
+            }
+        }
+    }
+
+    /// Whether `#` comment lines can precede the output, like the header of the command line
+    pub fn holds_comments(&self) -> bool {
+        match self {
+            #[cfg(feature = "grouped")]
+            Format::Grouped { .. } => true,
+            Format::Dotenv
+            | Format::Export { .. }
+            | Format::Tmux
+            | Format::Phpdotenv
+            | Format::ReloadFunction { .. } => true,
+            _ => false,
+        }
+    }
+}
+
+/// Write the variables in the given format as they are rendered, without buffering the whole output
+pub fn render_to(vars: &[EnvVar], format: &Format, writer: &mut impl Write) -> Result<(), Error> {
+    format.renderer().render(vars, writer)
+}
+
+/// Like [`render_to`], with each variable preceded by a `# /pointer` comment naming the value of the
+/// document it comes from.
+///
+/// Variables without a source pointer, like the inherited ones, get no comment. Fails for the
+/// formats that cannot hold comments.
+pub fn render_annotated_to(
+    vars: &[EnvVar],
+    format: &Format,
+    writer: &mut impl Write,
+) -> Result<(), Error> {
+    match format {
+        #[cfg(feature = "grouped")]
+        Format::Grouped { key_separator } => {
+            Grouped::new(key_separator.as_str()).render_groups(vars, writer, true)
+        }
+        Format::Dotenv | Format::Export { .. } | Format::Tmux | Format::Phpdotenv => {
+            write_annotated(vars, format.renderer().as_ref(), writer)
+        }
+        _ => Err(Error::InvalidOptions(
+            "The output format cannot hold comments".to_owned(),
+        )),
+    }
+}
+
+/// `.env` file with a `KEY=value` line per variable, the default format
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Dotenv;
+
+impl Renderer for Dotenv {
+    fn render(&self, vars: &[EnvVar], writer: &mut dyn Write) -> Result<(), Error> {
+        write_lines(vars, writer)
+    }
+}
+
+/// `.env` file read back verbatim by vlucas/phpdotenv, the parser of Laravel.
+///
+/// Strings are single quoted, where `#` does not start a comment and `${...}` is not expanded,
+/// the ones holding a `'` are double quoted with `\\`, `"` and `$` escaped.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Phpdotenv;
+
+impl Renderer for Phpdotenv {
+    fn render(&self, vars: &[EnvVar], writer: &mut dyn Write) -> Result<(), Error> {
+        for (index, var) in vars.iter().enumerate() {
+            if index > 0 {
+                writer.write_all(b"\n")?;
+            }
+
+            let key = var.key();
+            match var.value() {
+                Value::String(value) if !value.contains('\'') => write!(writer, "{key}='{value}'")?,
+                Value::String(value) => {
+                    let value = value
+                        .replace('\\', r"\\")
+                        .replace('"', r#"\""#)
+                        .replace('$', r"\$");
+                    write!(writer, r#"{key}="{value}""#)?
+                }
+                _ => write!(writer, "{key}={value}", value = unquoted(var)?)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Aligned and type annotated variables, see [`crate::pretty::pretty`]
+#[cfg(feature = "pretty")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Pretty {
+    color: bool,
+}
+
+#[cfg(feature = "pretty")]
+impl Pretty {
+    pub fn new(color: bool) -> Self {
+        Self { color }
+    }
+}
+
+#[cfg(feature = "pretty")]
+impl Renderer for Pretty {
+    fn render(&self, vars: &[EnvVar], writer: &mut dyn Write) -> Result<(), Error> {
+        writer.write_all(crate::pretty::pretty(vars, self.color).as_bytes())?;
+        Ok(())
+    }
+}
+
+/// `.env` file split in blocks by top-level key, each headed by a `# name` comment
+#[cfg(feature = "grouped")]
+#[derive(Debug, Clone)]
+pub struct Grouped {
+    key_separator: String,
+}
+
+#[cfg(feature = "grouped")]
+impl Grouped {
+    pub fn new(key_separator: impl Into<String>) -> Self {
+        Self {
+            key_separator: key_separator.into(),
+        }
+    }
+
+    fn render_groups(
+        &self,
+        vars: &[EnvVar],
+        writer: &mut dyn Write,
+        annotated: bool,
+    ) -> Result<(), Error> {
+        let groups = crate::group::group_by_top_level_key(vars, &self.key_separator);
+
+        for (index, (name, vars)) in groups.into_iter().enumerate() {
+            if index > 0 {
+                writer.write_all(b"\n\n")?;
+            }
+
+            // top-level values have no name to show
+            if !name.is_empty() {
+                writeln!(writer, "# {name}")?;
+            }
+
+            match annotated {
+                true => write_annotated(vars, &Dotenv, writer)?,
+                false => write_lines(vars, writer)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "grouped")]
+impl Renderer for Grouped {
+    fn render(&self, vars: &[EnvVar], writer: &mut dyn Write) -> Result<(), Error> {
+        self.render_groups(vars, writer, false)
+    }
+}
+
+/// `KEY=value` lines of the `$GITHUB_ENV` file of GitHub Actions, values are not quoted and
+/// multiline ones are enclosed in a heredoc delimiter
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GithubEnv;
+
+impl Renderer for GithubEnv {
+    fn render(&self, vars: &[EnvVar], writer: &mut dyn Write) -> Result<(), Error> {
+        for (index, var) in vars.iter().enumerate() {
+            if index > 0 {
+                writer.write_all(b"\n")?;
+            }
+
+            let (key, value) = (var.key(), unquoted(var)?);
+            match value.contains(['\n', '\r']) {
+                true => {
+                    // the delimiter must not appear in the value, or the value would end early
+                    let mut delimiter = "JSON2ENV_EOF".to_owned();
+                    while value.contains(&delimiter) {
+                        delimiter.push('_');
+                    }
+
+                    write!(writer, "{key}<<{delimiter}\n{value}\n{delimiter}")?
+                }
+                false => write!(writer, "{key}={value}")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// `##vso[task.setvariable]` logging commands of Azure Pipelines, written to STDOUT of a step
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AzureDevops;
+
+impl Renderer for AzureDevops {
+    fn render(&self, vars: &[EnvVar], writer: &mut dyn Write) -> Result<(), Error> {
+        for (index, var) in vars.iter().enumerate() {
+            if index > 0 {
+                writer.write_all(b"\n")?;
+            }
+
+            // escaping of the logging commands, properties also end at `;` and `]`
+            let key = var.key().replace(';', "%3B").replace(']', "%5D");
+            let value = unquoted(var)?
+                .replace('%', "%AZP25")
+                .replace('\r', "%0D")
+                .replace('\n', "%0A");
+
+            write!(writer, "##vso[task.setvariable variable={key}]{value}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// `export KEY='value'` shell commands, like the `$BASH_ENV` file of CircleCI.
+///
+/// Keys are written as they are, failing if they are not shell variable names. Readonly variables are followed by `; readonly KEY`, so that later assignments fail, sourcing the
+/// output twice in the same shell fails too.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Export {
+    readonly: bool,
+}
+
+impl Export {
+    pub fn new(readonly: bool) -> Self {
+        Self { readonly }
+    }
+}
+
+impl Renderer for Export {
+    fn render(&self, vars: &[EnvVar], writer: &mut dyn Write) -> Result<(), Error> {
+        for (index, var) in vars.iter().enumerate() {
+            if index > 0 {
+                writer.write_all(b"\n")?;
+            }
+
+            let value = shell_quoted(&unquoted(var)?);
+            let key = shell_name(var)?;
+            write!(writer, "export {key}={value}")?;
+            if self.readonly {
+                write!(writer, "; readonly {key}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Shell quoted `--build-arg 'KEY=value'` options of `docker build`, one per line or in a single line.
+///
+/// Build arguments are recorded in the history of the image, they must not hold secrets.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BuildArgs {
+    single_line: bool,
+}
+
+impl BuildArgs {
+    pub fn new(single_line: bool) -> Self {
+        Self { single_line }
+    }
+}
+
+impl Renderer for BuildArgs {
+    fn render(&self, vars: &[EnvVar], writer: &mut dyn Write) -> Result<(), Error> {
+        let separator: &[u8] = match self.single_line {
+            true => b" ",
+            false => b"\n",
+        };
+
+        for (index, var) in vars.iter().enumerate() {
+            if index > 0 {
+                writer.write_all(separator)?;
+            }
+
+            let option = shell_quoted(&format!(
+                "{key}={value}",
+                key = var.key(),
+                value = unquoted(var)?
+            ));
+            write!(writer, "--build-arg {option}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// `tmux set-environment -g KEY 'value'` commands, setting the variables of all the sessions.
+///
+/// Keys are written as they are, failing if they are not shell variable names like for [`Export`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Tmux;
+
+impl Renderer for Tmux {
+    fn render(&self, vars: &[EnvVar], writer: &mut dyn Write) -> Result<(), Error> {
+        for (index, var) in vars.iter().enumerate() {
+            if index > 0 {
+                writer.write_all(b"\n")?;
+            }
+
+            let value = shell_quoted(&unquoted(var)?);
+            let key = shell_name(var)?;
+            write!(writer, "tmux set-environment -g {key} {value}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// `export KEY='value'` shell commands followed by a `name()` shell function running the command
+/// again and evaluating its output, to refresh the variables of the session after the input changes.
+///
+/// The command is expected to write the variables as `export` commands, their keys are checked like
+/// for [`Export`] since the function evaluates them on every reload.
+#[derive(Debug, Clone, Default)]
+pub struct ReloadFunction {
+    name: String,
+    command: Vec<String>,
+}
+
+impl ReloadFunction {
+    pub fn new(name: impl Into<String>, command: Vec<String>) -> Self {
+        Self {
+            name: name.into(),
+            command,
+        }
+    }
+}
+
+impl Renderer for ReloadFunction {
+    fn render(&self, vars: &[EnvVar], writer: &mut dyn Write) -> Result<(), Error> {
+        Export::default().render(vars, writer)?;
+        if !vars.is_empty() {
+            writer.write_all(b"\n")?;
+        }
+
+        let command = self
+            .command
+            .iter()
+            .map(|word| shell_quoted(word))
+            .collect::<Vec<_>>()
+            .join(" ");
+        write!(writer, "{}() {{\n    eval \"$({command})\"\n}}", self.name)?;
+
+        Ok(())
+    }
+}
+
+/// `.reg` file setting the variables of the user under `HKCU\Environment`, imported by `regedit`.
+///
+/// The file is UTF-16LE with CRLF line endings like the exports of `regedit`, values spanning
+/// lines cannot be represented.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Registry;
+
+impl Renderer for Registry {
+    fn render(&self, vars: &[EnvVar], writer: &mut dyn Write) -> Result<(), Error> {
+        let escape = |text: &str| text.replace('\\', r"\\").replace('"', r#"\""#);
+
+        let mut content = "Windows Registry Editor Version 5.00\r\n\r\n".to_owned();
+        content.push_str("[HKEY_CURRENT_USER\\Environment]\r\n");
+        for var in vars {
+            let value = unquoted(var)?;
+            if value.contains(['\r', '\n']) {
+                return Err(Error::Render {
+                    key: var.key().to_owned(),
+                    reason: "values spanning lines are not supported".to_owned(),
+                });
+            }
+
+            let (key, value) = (escape(var.key()), escape(&value));
+            content.push_str(&format!("\"{key}\"=\"{value}\"\r\n"));
+        }
+
+        let bytes = std::iter::once(0xFEFF)
+            .chain(content.encode_utf16())
+            .flat_map(u16::to_le_bytes)
+            .collect::<Vec<_>>();
+        writer.write_all(&bytes)?;
+
+        Ok(())
+    }
+}
+
+/// Value of the variable as it is read back, failing for nested values like [`EnvVar::render`]
+fn unquoted(var: &EnvVar) -> Result<String, Error> {
+    match var.value() {
+        Value::Array(_) | Value::Object(_) => Err(Error::Render {
+            key: var.key().to_owned(),
+            reason: "nested values are not supported".to_owned(),
+        }),
+        _ => Ok(<(String, String)>::from(var).1),
+    }
+}
+
+/// Key of the variable, failing if it is not a shell variable name, the shell would run the other
+/// keys as commands
+fn shell_name(var: &EnvVar) -> Result<&str, Error> {
+    match Target::Posix.is_valid_name(var.key()) {
+        true => Ok(var.key()),
+        false => Err(Error::Render {
+            key: var.key().to_owned(),
+            reason: "the key is not a valid shell variable name".to_owned(),
+        }),
+    }
+}
+
+/// Single quoted shell word, closing the quotes around each `'`
+fn shell_quoted(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+/// Write the rendered variables one per line, without a trailing new line
+fn write_lines<'a>(
+    vars: impl IntoIterator<Item = &'a EnvVar>,
+    writer: &mut dyn Write,
+) -> Result<(), Error> {
+    for (index, var) in vars.into_iter().enumerate() {
+        if index > 0 {
+            writer.write_all(b"\n")?;
+        }
+
+        writer.write_all(var.render()?.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// Write the variables rendered one at a time, each preceded by a comment with its source pointer
+fn write_annotated<'a>(
+    vars: impl IntoIterator<Item = &'a EnvVar>,
+    renderer: &dyn Renderer,
+    writer: &mut dyn Write,
+) -> Result<(), Error> {
+    for (index, var) in vars.into_iter().enumerate() {
+        if index > 0 {
+            writer.write_all(b"\n")?;
+        }
+
+        // keys may hold line breaks, which would end the comment
+        if let Some(pointer) = var.source_pointer() {
+            let pointer = pointer.replace('\r', r"\r").replace('\n', r"\n");
+            writeln!(writer, "# {pointer}")?;
+        }
+
+        renderer.render(std::slice::from_ref(var), writer)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{
+        AzureDevops, BuildArgs, Dotenv, Export, GithubEnv, Phpdotenv, Registry, ReloadFunction,
+        Renderer, Tmux,
+    };
+    use crate::{EnvVar, Error};
+
+    fn render(renderer: &dyn Renderer, vars: &[EnvVar]) -> Result<String, Error> {
+        let mut buffer = Vec::new();
+        renderer.render(vars, &mut buffer)?;
+
+        Ok(String::from_utf8(buffer).unwrap())
+    }
+
+    #[test]
+    fn dotenv_should_write_a_line_per_variable() {
+        // ARRANGE
+        let vars = vec![EnvVar::new("A", "x"), EnvVar::new("B", 1)];
+
+        // ACT
+        let result = render(&Dotenv, &vars).unwrap();
+
+        // ASSERT
+        assert_eq!(result, "A=\"x\"\nB=1")
+    }
+
+    #[test]
+    fn dotenv_should_fail_for_nested_values() {
+        // ARRANGE
+        let vars = vec![EnvVar::new("A", json!({ "b": 1 }))];
+
+        // ACT
+        let result = render(&Dotenv, &vars);
+
+        // ASSERT
+        assert!(matches!(result, Err(Error::Render { .. })))
+    }
+
+    #[test]
+    fn github_env_should_delimit_multiline_values() {
+        // ARRANGE
+        let vars = vec![EnvVar::new("A", "x y"), EnvVar::new("B", "1\nJSON2ENV_EOF")];
+
+        // ACT
+        let result = render(&GithubEnv, &vars).unwrap();
+
+        // ASSERT
+        assert_eq!(
+            result,
+            "A=x y\nB<<JSON2ENV_EOF_\n1\nJSON2ENV_EOF\nJSON2ENV_EOF_"
+        )
+    }
+
+    #[test]
+    fn azure_devops_should_escape_logging_commands() {
+        // ARRANGE
+        let vars = vec![EnvVar::new("A;B", "50%\nx"), EnvVar::new("C", true)];
+
+        // ACT
+        let result = render(&AzureDevops, &vars).unwrap();
+
+        // ASSERT
+        assert_eq!(
+            result,
+            "##vso[task.setvariable variable=A%3BB]50%AZP25%0Ax\n##vso[task.setvariable variable=C]true"
+        )
+    }
+
+    #[test]
+    fn export_should_single_quote_values() {
+        // ARRANGE
+        let vars = vec![EnvVar::new("A", "it's $HOME"), EnvVar::new("B", 1)];
+
+        // ACT
+        let result = render(&Export::default(), &vars).unwrap();
+
+        // ASSERT
+        assert_eq!(result, "export A='it'\\''s $HOME'\nexport B='1'")
+    }
+
+    #[test]
+    fn export_should_mark_readonly_variables() {
+        // ARRANGE
+        let vars = vec![EnvVar::new("A", "x"), EnvVar::new("B", 1)];
+
+        // ACT
+        let result = render(&Export::new(true), &vars).unwrap();
+
+        // ASSERT
+        assert_eq!(result, "export A='x'; readonly A\nexport B='1'; readonly B")
+    }
+
+    #[test]
+    fn export_should_reject_keys_that_are_not_shell_names() {
+        // ARRANGE
+        let vars = vec![EnvVar::new("x; echo PWNED; y", 1)];
+
+        // ACT
+        let result = render(&Export::default(), &vars);
+
+        // ASSERT
+        assert!(matches!(result, Err(Error::Render { key, .. }) if key == "x; echo PWNED; y"))
+    }
+
+    #[test]
+    fn build_args_should_quote_options_on_lines_or_a_single_line() {
+        // ARRANGE
+        let vars = vec![EnvVar::new("A", "it's"), EnvVar::new("B", 1)];
+
+        // ACT
+        let result = [false, true].map(|single_line| render(&BuildArgs::new(single_line), &vars));
+
+        // ASSERT
+        assert_eq!(
+            result.map(Result::unwrap),
+            [
+                "--build-arg 'A=it'\\''s'\n--build-arg 'B=1'",
+                "--build-arg 'A=it'\\''s' --build-arg 'B=1'"
+            ]
+        )
+    }
+
+    #[test]
+    fn phpdotenv_should_keep_values_from_being_expanded() {
+        // ARRANGE
+        let vars = vec![
+            EnvVar::new("COLOR", "#fff"),
+            EnvVar::new("URL", "https://${HOST}/"),
+            EnvVar::new("QUOTE", r#"it's "$5" \o/"#),
+            EnvVar::new("DEBUG", true),
+        ];
+
+        // ACT
+        let result = render(&Phpdotenv, &vars).unwrap();
+
+        // ASSERT
+        assert_eq!(
+            result,
+            concat!(
+                "COLOR='#fff'\n",
+                "URL='https://${HOST}/'\n",
+                r#"QUOTE="it's \"\$5\" \\o/""#,
+                "\nDEBUG=true"
+            )
+        )
+    }
+
+    #[test]
+    fn tmux_should_write_set_environment_commands() {
+        // ARRANGE
+        let vars = vec![EnvVar::new("A", "it's"), EnvVar::new("B", true)];
+
+        // ACT
+        let result = render(&Tmux, &vars).unwrap();
+
+        // ASSERT
+        assert_eq!(
+            result,
+            "tmux set-environment -g A 'it'\\''s'\ntmux set-environment -g B 'true'"
+        )
+    }
+
+    #[test]
+    fn tmux_should_reject_keys_that_are_not_shell_names() {
+        // ARRANGE
+        let vars = vec![EnvVar::new("A B", 1)];
+
+        // ACT
+        let result = render(&Tmux, &vars);
+
+        // ASSERT
+        assert!(matches!(result, Err(Error::Render { key, .. }) if key == "A B"))
+    }
+
+    #[test]
+    fn reload_function_should_evaluate_the_command_again() {
+        // ARRANGE
+        let vars = vec![EnvVar::new("A", "x")];
+        let command = ["json2env", "-i", "/srv/app's.json", "-f", "export"].map(str::to_owned);
+        let renderer = ReloadFunction::new("app_env_reload", command.to_vec());
+
+        // ACT
+        let result = render(&renderer, &vars).unwrap();
+
+        // ASSERT
+        assert_eq!(
+            result,
+            concat!(
+                "export A='x'\n",
+                "app_env_reload() {\n",
+                "    eval \"$('json2env' '-i' '/srv/app'\\''s.json' '-f' 'export')\"\n",
+                "}"
+            )
+        )
+    }
+
+    #[test]
+    fn reload_function_should_reject_keys_that_are_not_shell_names() {
+        // ARRANGE
+        let vars = vec![EnvVar::new("$(id)", 1)];
+        let renderer = ReloadFunction::new("app_env_reload", vec!["json2env".to_owned()]);
+
+        // ACT
+        let result = render(&renderer, &vars);
+
+        // ASSERT
+        assert!(matches!(result, Err(Error::Render { key, .. }) if key == "$(id)"))
+    }
+
+    #[test]
+    fn registry_should_write_a_utf16_reg_file() {
+        // ARRANGE
+        let vars = vec![
+            EnvVar::new("PATH_EXT", r#"C:\"bin""#),
+            EnvVar::new("PORT", 1),
+        ];
+
+        // ACT
+        let mut bytes = Vec::new();
+        Registry.render(&vars, &mut bytes).unwrap();
+
+        // ASSERT
+        let units = bytes
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            String::from_utf16(&units).unwrap(),
+            concat!(
+                "\u{FEFF}Windows Registry Editor Version 5.00\r\n\r\n",
+                "[HKEY_CURRENT_USER\\Environment]\r\n",
+                "\"PATH_EXT\"=\"C:\\\\\\\"bin\\\"\"\r\n",
+                "\"PORT\"=\"1\"\r\n"
+            )
+        )
+    }
+
+    #[test]
+    fn registry_should_reject_values_spanning_lines() {
+        // ARRANGE
+        let vars = vec![EnvVar::new("A", "a\nb")];
+
+        // ACT
+        let result = Registry.render(&vars, &mut Vec::new());
+
+        // ASSERT
+        assert!(matches!(result, Err(Error::Render { key, .. }) if key == "A"))
+    }
+
+    #[cfg(feature = "grouped")]
+    #[test]
+    fn grouped_should_head_blocks_with_top_level_key() {
+        // ARRANGE
+        let vars = vec![
+            EnvVar::new("name", "app"),
+            EnvVar::new("db__host", "localhost"),
+            EnvVar::new("db__port", 5432),
+        ];
+
+        // ACT
+        let result = render(&super::Grouped::new("__"), &vars).unwrap();
+
+        // ASSERT
+        assert_eq!(
+            result,
+            "name=\"app\"\n\n# db\ndb__host=\"localhost\"\ndb__port=5432"
+        )
+    }
+
+    #[test]
+    fn render_annotated_to_should_precede_variables_with_their_source() {
+        // ARRANGE
+        let vars = vec![
+            EnvVar::new("db__host", "localhost").with_source_pointer("/db/host"),
+            EnvVar::new("a_b", 1).with_source_pointer("/a\nb"),
+            EnvVar::new("HOME", "/root"),
+        ];
+        let mut buffer = Vec::new();
+
+        // ACT
+        let format = super::Format::Export { readonly: false };
+        super::render_annotated_to(&vars, &format, &mut buffer).unwrap();
+
+        // ASSERT
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "# /db/host\nexport db__host='localhost'\n# /a\\nb\nexport a_b='1'\nexport HOME='/root'"
+        )
+    }
+
+    #[test]
+    fn render_annotated_to_should_fail_for_formats_without_comments() {
+        // ARRANGE
+        let vars = vec![EnvVar::new("A", 1).with_source_pointer("/A")];
+
+        // ACT
+        let result = super::render_annotated_to(&vars, &super::Format::GithubEnv, &mut Vec::new());
+
+        // ASSERT
+        assert!(matches!(result, Err(Error::InvalidOptions(_))))
+    }
+
+    #[test]
+    fn holds_comments_should_exclude_the_formats_read_verbatim() {
+        // ARRANGE
+        let formats = [
+            super::Format::Dotenv,
+            super::Format::Export { readonly: false },
+            super::Format::GithubEnv,
+            super::Format::AzureDevops,
+        ];
+
+        // ACT
+        let result = formats.map(|format| format.holds_comments());
+
+        // ASSERT
+        assert_eq!(result, [true, true, false, false])
+    }
+
+    #[cfg(feature = "grouped")]
+    #[test]
+    fn render_to_should_use_the_format_renderer() {
+        // ARRANGE
+        let vars = vec![EnvVar::new("db__host", "localhost")];
+        let format = super::Format::Grouped {
+            key_separator: "__".to_owned(),
+        };
+        let mut buffer = Vec::new();
+
+        // ACT
+        super::render_to(&vars, &format, &mut buffer).unwrap();
+
+        // ASSERT
+        assert_eq!(
+            buffer,
+            render(&super::Grouped::new("__"), &vars)
+                .unwrap()
+                .as_bytes()
+        )
+    }
+}
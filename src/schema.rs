@@ -0,0 +1,32 @@
+use json2env::{input, Error};
+use serde_json::Value;
+
+/// Check the document read from `input` against the JSON Schema in the `schema` file, logging
+/// every violation with the JSON pointer of the offending value
+pub fn validate(json: &Value, input: &str, schema: &str) -> Result<(), Error> {
+    let content = std::fs::read_to_string(schema)
+        .inspect_err(|_| log::error!("Could not read `{schema}`"))?;
+
+    let document = input::parse(&content)
+        .inspect_err(|_| log::error!("`{schema}` does not contain valid JSON"))?;
+
+    let validator = jsonschema::validator_for(&document).map_err(|error| {
+        Error::InvalidOptions(format!("`{schema}` is not a valid schema: {error}"))
+    })?;
+
+    let mut valid = true;
+    for error in validator.iter_errors(json) {
+        valid = false;
+        match error.instance_path().to_string() {
+            path if path.is_empty() => log::error!("`{input}`: {error}"),
+            path => log::error!("`{input}` at `{path}`: {error}"),
+        }
+    }
+
+    match valid {
+        true => Ok(()),
+        false => Err(Error::Validation(format!(
+            "`{input}` does not match the schema `{schema}`"
+        ))),
+    }
+}
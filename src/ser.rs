@@ -0,0 +1,747 @@
+use serde::{
+    ser::{self, Impossible},
+    Serialize,
+};
+use serde_json::Value;
+
+use crate::{EmptyValues, EnvVar, Error, Flatten, JsonParser, ParseOptions};
+
+/// Flatten any serializable value to variables, without building a [`Value`] tree first.
+///
+/// The variables are the same as parsing the JSON representation of the value with
+/// [`JsonParser::parse`], struct fields are listed in declaration order instead of sorted.
+/// Fails for the options needing the document: empty values that are not skipped, array
+/// lengths, nested values kept as JSON, a maximum depth, keys by field, separators of the
+/// first levels and array separators by pointer.
+pub fn to_vars<T: Serialize + ?Sized>(
+    value: &T,
+    options: &ParseOptions,
+) -> Result<Vec<EnvVar>, Error> {
+    let unsupported = [
+        (
+            options.empty_values != EmptyValues::Skip,
+            "kept empty values",
+        ),
+        (options.array_length.is_some(), "array lengths"),
+        (
+            matches!(options.flatten, Some(Flatten::Objects | Flatten::Arrays)),
+            "nested values kept as JSON",
+        ),
+        (options.max_depth.is_some(), "a maximum depth"),
+        (options.key_by.is_some(), "arrays keyed by a field"),
+        (
+            !options.key_separators.is_empty(),
+            "separators of the first levels",
+        ),
+        (
+            !options.array_separators.is_empty(),
+            "array separators by pointer",
+        ),
+    ];
+    if let Some((_, option)) = unsupported.iter().find(|(set, _)| *set) {
+        let message = format!("Serialized values cannot have {option}");
+        return Err(Error::InvalidOptions(message));
+    }
+
+    let mut serializer = EnvSerializer::new(options);
+    value.serialize(&mut serializer)?;
+
+    Ok(serializer.into_vars())
+}
+
+/// Serializer collecting the leaves of the value as variables, keyed by their path
+pub struct EnvSerializer<'a> {
+    options: &'a ParseOptions,
+    /// Key of the value being serialized, extended and truncated while descending
+    key: String,
+    vars: Vec<EnvVar>,
+    /// Whether the last serialized value was a sequence or a map
+    nested: bool,
+}
+
+impl<'a> EnvSerializer<'a> {
+    pub fn new(options: &'a ParseOptions) -> Self {
+        Self {
+            options,
+            key: String::new(),
+            vars: Vec::new(),
+            nested: false,
+        }
+    }
+
+    pub fn into_vars(self) -> Vec<EnvVar> {
+        self.vars
+    }
+
+    /// Append a segment to the current key, returning the length to truncate it back to
+    fn push_segment(&mut self, segment: &str) -> usize {
+        let len = self.key.len();
+        if !self.key.is_empty() {
+            self.key.push_str(&self.options.key_separator);
+        }
+        self.key.push_str(segment);
+
+        len
+    }
+
+    /// Append the index of an element to the current key, see [`EnvSerializer::push_segment`]
+    fn push_index(&mut self, index: usize) -> usize {
+        let len = self.key.len();
+        let separator = &self.options.key_separator;
+        self.options.push_index(&mut self.key, index, separator);
+
+        len
+    }
+
+    fn leaf(&mut self, value: Value) -> Result<(), Error> {
+        self.nested = false;
+        self.vars
+            .push(EnvVar::new(self.key.trim().to_owned(), value));
+        Ok(())
+    }
+
+    fn seq(&mut self, restore: usize) -> Seq<'_, 'a> {
+        self.nested = true;
+        Seq {
+            start: self.vars.len(),
+            serializer: self,
+            restore,
+            index: 0,
+            nested: false,
+        }
+    }
+
+    fn map(&mut self, restore: usize) -> Map<'_, 'a> {
+        self.nested = true;
+        Map {
+            len: self.key.len(),
+            serializer: self,
+            restore,
+            key: None,
+        }
+    }
+}
+
+impl<'s, 'a> ser::Serializer for &'s mut EnvSerializer<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Seq<'s, 'a>;
+    type SerializeTuple = Seq<'s, 'a>;
+    type SerializeTupleStruct = Seq<'s, 'a>;
+    type SerializeTupleVariant = Seq<'s, 'a>;
+    type SerializeMap = Map<'s, 'a>;
+    type SerializeStruct = Map<'s, 'a>;
+    type SerializeStructVariant = Map<'s, 'a>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), Error> {
+        self.leaf(Value::from(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), Error> {
+        self.leaf(Value::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), Error> {
+        self.leaf(Value::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), Error> {
+        self.leaf(Value::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), Error> {
+        self.leaf(Value::from(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), Error> {
+        self.leaf(Value::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), Error> {
+        self.leaf(Value::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), Error> {
+        self.leaf(Value::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), Error> {
+        self.leaf(Value::from(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), Error> {
+        self.leaf(Value::from(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), Error> {
+        self.leaf(Value::from(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), Error> {
+        self.leaf(Value::from(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), Error> {
+        self.leaf(Value::from(v))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), Error> {
+        let mut seq = ser::Serializer::serialize_seq(self, Some(v.len()))?;
+        for byte in v {
+            ser::SerializeSeq::serialize_element(&mut seq, byte)?;
+        }
+
+        ser::SerializeSeq::end(seq)
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        self.leaf(Value::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        self.leaf(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> {
+        self.leaf(Value::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), Error> {
+        self.leaf(Value::from(variant))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        // externally tagged like JSON: `{ "variant": value }`
+        let restore = self.push_segment(variant);
+        value.serialize(&mut *self)?;
+        self.key.truncate(restore);
+        self.nested = true;
+
+        Ok(())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Seq<'s, 'a>, Error> {
+        let restore = self.key.len();
+        Ok(self.seq(restore))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Seq<'s, 'a>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<Seq<'s, 'a>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Seq<'s, 'a>, Error> {
+        let restore = self.push_segment(variant);
+        Ok(self.seq(restore))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Map<'s, 'a>, Error> {
+        let restore = self.key.len();
+        Ok(self.map(restore))
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Map<'s, 'a>, Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Map<'s, 'a>, Error> {
+        let restore = self.push_segment(variant);
+        Ok(self.map(restore))
+    }
+}
+
+/// Sequence being serialized, elements are enumerated and joined at the end if all scalars
+pub struct Seq<'s, 'a> {
+    serializer: &'s mut EnvSerializer<'a>,
+    /// Length of the key before the sequence, shorter than the current one for tuple variants
+    restore: usize,
+    /// First variable produced by the sequence
+    start: usize,
+    index: usize,
+    /// Whether any element is a sequence or a map
+    nested: bool,
+}
+
+impl Seq<'_, '_> {
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let len = self.serializer.push_index(self.index);
+        value.serialize(&mut *self.serializer)?;
+        self.serializer.key.truncate(len);
+
+        self.nested |= self.serializer.nested;
+        self.index += 1;
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), Error> {
+        let serializer = self.serializer;
+        let options = serializer.options;
+
+        // complex (nested) values cannot be part of an array enumeration, skip just this array
+        if !options.enumerate_array && !self.nested {
+            let values = serializer
+                .vars
+                .drain(self.start..)
+                .map(|var| var.1)
+                .collect::<Vec<_>>();
+
+            let value = JsonParser::join_array(&values, &options.array_separator);
+            serializer.leaf(value)?;
+        }
+
+        serializer.key.truncate(self.restore);
+        serializer.nested = true;
+        Ok(())
+    }
+}
+
+impl ser::SerializeSeq for Seq<'_, '_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for Seq<'_, '_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for Seq<'_, '_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for Seq<'_, '_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+/// Map or struct being serialized, each entry extends the key with its name
+pub struct Map<'s, 'a> {
+    serializer: &'s mut EnvSerializer<'a>,
+    /// Length of the key before the map, shorter than the current one for struct variants
+    restore: usize,
+    /// Length of the key of the map itself
+    len: usize,
+    /// Key of the entry whose value is yet to be serialized
+    key: Option<String>,
+}
+
+impl Map<'_, '_> {
+    fn entry<T: Serialize + ?Sized>(&mut self, name: &str, value: &T) -> Result<(), Error> {
+        self.serializer.push_segment(name);
+        value.serialize(&mut *self.serializer)?;
+        self.serializer.key.truncate(self.len);
+
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), Error> {
+        self.serializer.key.truncate(self.restore);
+        self.serializer.nested = true;
+        Ok(())
+    }
+}
+
+impl ser::SerializeMap for Map<'_, '_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        self.key = Some(key.serialize(KeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self
+            .key
+            .take()
+            .expect("serialize_value called before serialize_key");
+        self.entry(&key, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for Map<'_, '_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.entry(key, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for Map<'_, '_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.entry(key, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+/// Serializer of map keys, which like in JSON must be strings or numbers
+struct KeySerializer;
+
+fn key_error() -> Error {
+    Error::InvalidInput("map keys must be strings or numbers".into())
+}
+
+impl ser::Serializer for KeySerializer {
+    type Ok = String;
+    type Error = Error;
+
+    type SerializeSeq = Impossible<String, Error>;
+    type SerializeTuple = Impossible<String, Error>;
+    type SerializeTupleStruct = Impossible<String, Error>;
+    type SerializeTupleVariant = Impossible<String, Error>;
+    type SerializeMap = Impossible<String, Error>;
+    type SerializeStruct = Impossible<String, Error>;
+    type SerializeStructVariant = Impossible<String, Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<String, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<String, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_char(self, v: char) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<String, Error> {
+        Ok(v.to_owned())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<String, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_none(self) -> Result<String, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<String, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_unit(self) -> Result<String, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<String, Error> {
+        Ok(variant.to_owned())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<String, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Err(key_error())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde::Serialize;
+
+    use super::to_vars;
+    use crate::{EmptyValues, Error, Flatten, JsonParser, ParseOptions};
+
+    #[derive(Serialize)]
+    struct Database {
+        host: String,
+        port: u16,
+        password: Option<String>,
+    }
+
+    #[derive(Serialize)]
+    enum Mode {
+        Fast,
+        Limit(u32),
+    }
+
+    #[derive(Serialize)]
+    struct Config {
+        db: Database,
+        hosts: Vec<&'static str>,
+        servers: Vec<BTreeMap<&'static str, &'static str>>,
+        empty: BTreeMap<String, String>,
+        modes: (Mode, Mode),
+        labels: BTreeMap<u8, bool>,
+    }
+
+    fn config() -> Config {
+        Config {
+            db: Database {
+                host: "localhost".to_owned(),
+                port: 5432,
+                password: None,
+            },
+            hosts: vec!["a", "b"],
+            servers: vec![BTreeMap::from([("name", "web")])],
+            empty: BTreeMap::new(),
+            modes: (Mode::Fast, Mode::Limit(10)),
+            labels: BTreeMap::from([(1, true)]),
+        }
+    }
+
+    #[test]
+    fn to_vars_should_match_parsing_the_json_representation() {
+        // ARRANGE
+        let joined = ParseOptions::default();
+        let enumerated = ParseOptions::builder().enumerate_array(true).build();
+        let flattened = ParseOptions::builder().flatten(Flatten::All).build();
+        let indexed = ParseOptions::builder()
+            .enumerate_array(true)
+            .index_brackets(true)
+            .index_start(1)
+            .index_width(2)
+            .build();
+        let json = serde_json::to_value(config()).unwrap();
+
+        for options in [joined, enumerated, flattened, indexed] {
+            // ACT
+            let mut result = to_vars(&config(), &options).unwrap();
+
+            // ASSERT
+            let mut expected = JsonParser::new(options).parse(&json);
+            result.sort_by(|a, b| a.0.cmp(&b.0));
+            expected.sort_by(|a, b| a.0.cmp(&b.0));
+
+            assert_eq!(result, expected)
+        }
+    }
+
+    #[test]
+    fn to_vars_should_reject_the_options_needing_the_document() {
+        // ARRANGE
+        let builder = ParseOptions::builder;
+        let options = [
+            builder().empty_values(EmptyValues::Literal).build(),
+            builder().array_length("COUNT").build(),
+            builder().flatten(Flatten::Objects).build(),
+            builder().max_depth(3).build(),
+            builder().key_by("name").build(),
+            builder().key_separator_levels(["."]).build(),
+            builder().array_separator_for("/hosts", ";").build(),
+        ];
+
+        // ACT
+        let result = options.map(|options| to_vars(&config(), &options));
+
+        // ASSERT
+        for result in result {
+            assert!(matches!(result, Err(Error::InvalidOptions(_))))
+        }
+    }
+
+    #[test]
+    fn to_vars_should_fail_on_non_scalar_map_keys() {
+        // ARRANGE
+        let value = BTreeMap::from([((1, 2), "tuple")]);
+
+        // ACT
+        let result = to_vars(&value, &ParseOptions::default());
+
+        // ASSERT
+        assert!(result.is_err())
+    }
+}
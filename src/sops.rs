@@ -0,0 +1,53 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use json2env::Error;
+
+/// Encrypt the content of a dotenv file with the `sops` program, for the age recipients and the
+/// AWS KMS keys.
+///
+/// The plaintext only goes through the standard input of `sops`, it is never written to disk.
+pub fn encrypt(plain: &[u8], age: &[String], kms: &[String]) -> Result<Vec<u8>, Error> {
+    let mut command = Command::new("sops");
+    command.args([
+        "--encrypt",
+        "--input-type",
+        "dotenv",
+        "--output-type",
+        "dotenv",
+    ]);
+
+    if !age.is_empty() {
+        command.args(["--age", &age.join(",")]);
+    }
+
+    if !kms.is_empty() {
+        command.args(["--kms", &kms.join(",")]);
+    }
+
+    let mut child = command
+        .arg("/dev/stdin")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .inspect_err(|_| log::error!("Could not execute `sops`, is it installed?"))?;
+
+    // sops reads the whole input before writing, the pipe is closed when stdin is dropped
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(plain)
+        .inspect_err(|_| log::error!("Could not write the variables to `sops`"))?;
+
+    let output = child.wait_with_output()?;
+    match output.status.success() {
+        true => Ok(output.stdout),
+        false => {
+            log::error!("`sops` could not encrypt the variables");
+            Err(std::io::Error::other(format!("`sops` exited with {}", output.status)).into())
+        }
+    }
+}
@@ -0,0 +1,497 @@
+use std::{fmt, io::Read};
+
+use serde::de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde_json::Value;
+
+use crate::{
+    input::{Encoding, UTF8_BOM},
+    EmptyValues, EnvVar, Error, Flatten, JsonParser, ParseOptions,
+};
+
+/// Parse the document while it is read, passing each variable to `emit` as soon as it is known.
+///
+/// Unlike [`JsonParser::parse`], the document is never held in memory: only the arrays of scalars
+/// waiting to be joined are buffered. Keys are emitted in document order instead of sorted, and
+/// warnings are not reported. The reader is not buffered, wrap files in a `BufReader`.
+///
+/// A leading UTF-8 byte order mark is skipped, UTF-16 documents cannot be streamed and must be
+/// decoded with [`crate::input::decode`] first. Stops at the first error returned by `emit`.
+///
+/// Fails if arrays are keyed by a field, since their elements are emitted before it is read, or if
+/// nested values are kept as JSON strings.
+pub fn parse_reader<R, F>(mut reader: R, options: &ParseOptions, emit: F) -> Result<(), Error>
+where
+    R: Read,
+    F: FnMut(EnvVar) -> Result<(), Error>,
+{
+    if options.key_by.is_some() {
+        let message = "Arrays cannot be keyed by a field when streaming".to_owned();
+        return Err(Error::InvalidOptions(message));
+    }
+
+    if matches!(options.flatten, Some(Flatten::Objects | Flatten::Arrays)) {
+        let message = "Nested values cannot be kept as JSON when streaming".to_owned();
+        return Err(Error::InvalidOptions(message));
+    }
+
+    let mut start = Vec::with_capacity(UTF8_BOM.len());
+    reader
+        .by_ref()
+        .take(UTF8_BOM.len() as u64)
+        .read_to_end(&mut start)?;
+
+    if Encoding::detect(&start) != Encoding::Utf8 {
+        let message = "UTF-16 documents cannot be streamed";
+        return Err(Error::InvalidInput(message.into()));
+    }
+
+    let start = start.strip_prefix(UTF8_BOM).unwrap_or(&start);
+    let reader = start.chain(reader);
+
+    let mut state = State {
+        options,
+        key: String::new(),
+        pointer: String::new(),
+        depth: 0,
+        emit,
+        error: None,
+    };
+
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let root = Node {
+        state: &mut state,
+        array: None,
+    }
+    .deserialize(&mut deserializer);
+
+    match root {
+        Ok(Kind::Scalar(value)) => state.leaf(value)?,
+        Ok(Kind::Nested) => {}
+        // errors of `emit` are reported as they are, not as JSON errors
+        Err(error) => return Err(state.error.take().unwrap_or_else(|| error.into())),
+    }
+
+    deserializer.end()?;
+    Ok(())
+}
+
+struct State<'a, F> {
+    options: &'a ParseOptions,
+    /// Key of the value being read, extended and truncated while descending
+    key: String,
+    /// JSON pointer of the value being read, reported when it is nested too deep
+    pointer: String,
+    /// Number of objects and arrays containing the value being read
+    depth: usize,
+    emit: F,
+    /// Error returned by `emit`, which is reported to serde as a message only
+    error: Option<Error>,
+}
+
+impl<'a, F: FnMut(EnvVar) -> Result<(), Error>> State<'a, F> {
+    /// Descend into a member, returning the lengths to truncate the key and pointer to afterwards
+    fn push_name(&mut self, name: &str) -> (usize, usize) {
+        let lens = (self.key.len(), self.pointer.len());
+        if !self.key.is_empty() {
+            self.key.push_str(self.separator());
+        }
+        self.key.push_str(name);
+
+        JsonParser::push_pointer_segment(&mut self.pointer, name);
+
+        lens
+    }
+
+    /// Descend into an element, see [`State::push_name`]
+    fn push_index(&mut self, index: usize) -> (usize, usize) {
+        let lens = (self.key.len(), self.pointer.len());
+        let separator = self.separator();
+        self.options.push_index(&mut self.key, index, separator);
+        JsonParser::push_pointer_segment(&mut self.pointer, &index.to_string());
+
+        lens
+    }
+
+    /// Separator following the key of the object or array being read, which has a segment per
+    /// level above it
+    fn separator(&self) -> &'a str {
+        self.options.key_separator_at(self.depth.saturating_sub(2))
+    }
+
+    fn pop_segment(&mut self, (key, pointer): (usize, usize)) {
+        self.key.truncate(key);
+        self.pointer.truncate(pointer);
+    }
+
+    /// Enter an object or array, unless it is nested deeper than allowed
+    fn descend(&mut self) -> Result<(), Error> {
+        if let Some(limit) = self.options.max_depth.filter(|&limit| self.depth >= limit) {
+            return Err(Error::TooDeep {
+                pointer: self.pointer.clone(),
+                limit,
+            });
+        }
+
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn leaf(&mut self, value: Value) -> Result<(), Error> {
+        let var = EnvVar::new(self.key.trim(), value);
+        (self.emit)(var)
+    }
+
+    /// Variable representing an empty object or array, unless skipped
+    fn empty(&mut self, literal: &str) -> Result<(), Error> {
+        match self.options.empty_values {
+            _ if self.key.is_empty() => Ok(()),
+            EmptyValues::Skip => Ok(()),
+            EmptyValues::EmptyString => self.leaf(Value::from("")),
+            EmptyValues::Literal => self.leaf(Value::from(literal)),
+        }
+    }
+
+    fn fail<E: de::Error>(&mut self, error: Error) -> E {
+        let message = error.to_string();
+        self.error = Some(error);
+        E::custom(message)
+    }
+}
+
+/// Array being read, whose scalars are buffered until it is known whether they can be joined
+struct Array {
+    key: String,
+    /// Level of the separator following the key of the array
+    level: usize,
+    scalars: Vec<Value>,
+    enumerated: bool,
+}
+
+impl Array {
+    /// Emit the buffered scalars as enumerated, since a nested value has been found
+    fn enumerate<F: FnMut(EnvVar) -> Result<(), Error>>(
+        &mut self,
+        state: &mut State<F>,
+    ) -> Result<(), Error> {
+        if self.enumerated {
+            return Ok(());
+        }
+
+        self.enumerated = true;
+        for (index, value) in std::mem::take(&mut self.scalars).into_iter().enumerate() {
+            let mut key = self.key.clone();
+            let separator = state.options.key_separator_at(self.level);
+            state.options.push_index(&mut key, index, separator);
+            (state.emit)(EnvVar::new(key.trim(), value))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// What a value turned out to be once read
+enum Kind {
+    /// Scalars are emitted by their parent, which knows whether to join them
+    Scalar(Value),
+    /// Objects and arrays emit their own variables
+    Nested,
+}
+
+struct Node<'s, 'a, F> {
+    state: &'s mut State<'a, F>,
+    /// Array containing the value, if any
+    array: Option<&'s mut Array>,
+}
+
+impl<F: FnMut(EnvVar) -> Result<(), Error>> Node<'_, '_, F> {
+    /// Nested values force the enumeration of their array, before any of their variables
+    fn nested<E: de::Error>(&mut self) -> Result<(), E> {
+        let entered = match self.array {
+            Some(ref mut array) => array.enumerate(self.state),
+            None => Ok(()),
+        };
+
+        entered
+            .and_then(|_| self.state.descend())
+            .map_err(|error| self.state.fail(error))
+    }
+}
+
+impl<'de, F: FnMut(EnvVar) -> Result<(), Error>> DeserializeSeed<'de> for Node<'_, '_, F> {
+    type Value = Kind;
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<Kind, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, F: FnMut(EnvVar) -> Result<(), Error>> Visitor<'de> for Node<'_, '_, F> {
+    type Value = Kind;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a JSON value")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Kind, E> {
+        Ok(Kind::Scalar(Value::Null))
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Kind, E> {
+        Ok(Kind::Scalar(Value::from(v)))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Kind, E> {
+        Ok(Kind::Scalar(Value::from(v)))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Kind, E> {
+        Ok(Kind::Scalar(Value::from(v)))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Kind, E> {
+        Ok(Kind::Scalar(Value::from(v)))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Kind, E> {
+        Ok(Kind::Scalar(Value::from(v)))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Kind, E> {
+        Ok(Kind::Scalar(Value::from(v)))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(mut self, mut map: A) -> Result<Kind, A::Error> {
+        self.nested()?;
+        let state = self.state;
+
+        let mut empty = true;
+        while let Some(name) = map.next_key::<String>()? {
+            empty = false;
+            let lens = state.push_name(&name);
+            let child = Node {
+                state: &mut *state,
+                array: None,
+            };
+
+            if let Kind::Scalar(value) = map.next_value_seed(child)? {
+                state.leaf(value).map_err(|error| state.fail(error))?;
+            }
+            state.pop_segment(lens);
+        }
+
+        if empty {
+            state.empty("{}").map_err(|error| state.fail(error))?;
+        }
+
+        state.depth -= 1;
+        Ok(Kind::Nested)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> Result<Kind, A::Error> {
+        self.nested()?;
+        let state = self.state;
+
+        let mut array = Array {
+            key: state.key.clone(),
+            level: state.depth.saturating_sub(2),
+            scalars: Vec::new(),
+            enumerated: state.options.enumerate_array,
+        };
+
+        let mut count = 0;
+        for index in 0.. {
+            count = index;
+            let lens = state.push_index(index);
+            let child = Node {
+                state: &mut *state,
+                array: Some(&mut array),
+            };
+
+            let element = seq.next_element_seed(child)?;
+            match element {
+                Some(Kind::Scalar(value)) if array.enumerated => {
+                    state.leaf(value).map_err(|error| state.fail(error))?
+                }
+                Some(Kind::Scalar(value)) => array.scalars.push(value),
+                Some(Kind::Nested) => {}
+                None => {
+                    state.pop_segment(lens);
+                    break;
+                }
+            }
+            state.pop_segment(lens);
+        }
+
+        let literal = state.options.empty_values == EmptyValues::Literal;
+        if count == 0 && (array.enumerated || literal) {
+            state.empty("[]").map_err(|error| state.fail(error))?;
+        } else if !array.enumerated {
+            // no nested value was found, the array can be joined
+            let separator = state.options.array_separator_at(&state.pointer);
+            let value = JsonParser::join_array(&array.scalars, separator);
+            state.leaf(value).map_err(|error| state.fail(error))?;
+        }
+
+        let options = state.options;
+        if let Some(name) = options.array_length.as_deref().filter(|_| array.enumerated) {
+            let lens = state.push_name(name);
+            state
+                .leaf(Value::from(count))
+                .map_err(|error| state.fail(error))?;
+            state.pop_segment(lens);
+        }
+
+        state.depth -= 1;
+        Ok(Kind::Nested)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::parse_reader;
+    use crate::{EmptyValues, EnvVar, Error, Flatten, JsonParser, ParseOptions};
+
+    fn stream(json: &str, options: &ParseOptions) -> Result<Vec<EnvVar>, Error> {
+        let mut vars = Vec::new();
+        parse_reader(json.as_bytes(), options, |var| {
+            vars.push(var);
+            Ok(())
+        })?;
+
+        Ok(vars)
+    }
+
+    #[test]
+    fn parse_reader_should_match_parse() {
+        // ARRANGE
+        let json = json!({
+            "db": { "host": "localhost", "port": 5432, "ssl": false, "password": null },
+            "hosts": ["a", "b"],
+            "mixed": [1, { "name": "web" }, [2, 3], []],
+            "empty": { "object": {}, "array": [] },
+            " padded ": "x"
+        });
+        let builder = ParseOptions::builder;
+        let all_options = [
+            builder().build(),
+            builder()
+                .enumerate_array(true)
+                .index_start(1)
+                .array_length("COUNT")
+                .build(),
+            builder()
+                .enumerate_array(true)
+                .index_format("{key}_{i}_".parse().unwrap())
+                .build(),
+            builder()
+                .empty_values(EmptyValues::Literal)
+                .array_separator_for("/hosts", ";")
+                .build(),
+            builder()
+                .enumerate_array(true)
+                .index_brackets(true)
+                .index_width(3)
+                .empty_values(EmptyValues::EmptyString)
+                .build(),
+            builder()
+                .key_separator_levels([".", "_"])
+                .array_length("COUNT")
+                .build(),
+        ];
+
+        for options in all_options {
+            // ACT
+            let mut result = stream(&json.to_string(), &options).unwrap();
+
+            // ASSERT
+            // the document is only sorted when serde_json does not preserve the order of keys
+            let mut expected = JsonParser::new(options).parse(&json);
+            result.sort();
+            expected.sort();
+            assert_eq!(result, expected)
+        }
+    }
+
+    #[test]
+    fn parse_reader_should_fail_when_too_deep() {
+        // ARRANGE
+        let json = r#"{ "a": { "b": [1, 2] }, "c": [{ "d": [3] }] }"#;
+        let options = ParseOptions::builder().max_depth(3).build();
+
+        // ACT
+        let result = stream(json, &options);
+
+        // ASSERT
+        assert!(matches!(result, Err(Error::TooDeep { pointer, limit: 3 }) if pointer == "/c/0/d"))
+    }
+
+    #[test]
+    fn parse_reader_should_skip_the_byte_order_mark() {
+        // ARRANGE
+        let json = "\u{FEFF}{ \"a\": 1 }";
+
+        // ACT
+        let result = stream(json, &ParseOptions::default()).unwrap();
+
+        // ASSERT
+        assert_eq!(result, vec![EnvVar::new("a", 1)])
+    }
+
+    #[test]
+    fn parse_reader_should_stop_on_emit_error() {
+        // ARRANGE
+        let json = r#"{ "a": 1, "b": 2 }"#;
+        let mut count = 0;
+
+        // ACT
+        let result = parse_reader(json.as_bytes(), &ParseOptions::default(), |_| {
+            count += 1;
+            Err(Error::Validation("stop".to_owned()))
+        });
+
+        // ASSERT
+        assert!(matches!(result, Err(Error::Validation(_))));
+        assert_eq!(count, 1)
+    }
+
+    #[test]
+    fn parse_reader_should_fail_on_trailing_content() {
+        // ARRANGE
+        let json = r#"{ "a": 1 } x"#;
+
+        // ACT
+        let result = stream(json, &ParseOptions::default());
+
+        // ASSERT
+        assert!(matches!(result, Err(Error::InvalidInput(_))))
+    }
+
+    #[test]
+    fn parse_reader_should_reject_arrays_keyed_by_field() {
+        // ARRANGE
+        let options = ParseOptions::builder().key_by("name").build();
+
+        // ACT
+        let result = stream("[]", &options);
+
+        // ASSERT
+        assert!(matches!(result, Err(Error::InvalidOptions(_))))
+    }
+
+    #[test]
+    fn parse_reader_should_reject_nested_values_kept_as_json() {
+        // ARRANGE
+        let options = [Flatten::Objects, Flatten::Arrays, Flatten::All]
+            .map(|flatten| ParseOptions::builder().flatten(flatten).build());
+
+        // ACT
+        let result = options.map(|options| stream(r#"{ "a": [1] }"#, &options));
+
+        // ASSERT
+        assert!(matches!(result[0], Err(Error::InvalidOptions(_))));
+        assert!(matches!(result[1], Err(Error::InvalidOptions(_))));
+        assert!(result[2].is_ok())
+    }
+}
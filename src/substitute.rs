@@ -0,0 +1,78 @@
+use serde_json::{Map, Value};
+
+use crate::{EnvVar, Error};
+
+/// Replace the string values equal to a placeholder of the mapping, like `"<VAULT:db_password>"`,
+/// with the value it maps to.
+///
+/// Fails without replacing anything if the mapping holds nested values, which variables cannot
+/// represent.
+pub fn substitute(vars: &mut [EnvVar], mapping: &Map<String, Value>) -> Result<(), Error> {
+    if let Some((placeholder, _)) = mapping
+        .iter()
+        .find(|(_, value)| value.is_array() || value.is_object())
+    {
+        let message = format!("The placeholder `{placeholder}` must map to a scalar value");
+        return Err(Error::InvalidInput(message.into()));
+    }
+
+    for var in vars.iter_mut() {
+        let Value::String(ref value) = var.1 else {
+            continue;
+        };
+
+        if let Some(replacement) = mapping.get(value) {
+            var.1 = replacement.clone();
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::substitute;
+    use crate::{EnvVar, Error};
+
+    #[test]
+    fn substitute_should_replace_placeholders() {
+        // ARRANGE
+        let mut vars = vec![
+            EnvVar::new("DB__PASSWORD", "<VAULT:db_password>"),
+            EnvVar::new("DB__PORT", "<VAULT:db_port>"),
+            EnvVar::new("DB__HOST", "localhost <VAULT:db_password>"),
+            EnvVar::new("DB__UNKNOWN", "<VAULT:unknown>"),
+        ];
+        let mapping = json!({ "<VAULT:db_password>": "s3cret", "<VAULT:db_port>": 5432 });
+
+        // ACT
+        substitute(&mut vars, mapping.as_object().unwrap()).unwrap();
+
+        // ASSERT
+        assert_eq!(
+            vars,
+            vec![
+                EnvVar::new("DB__PASSWORD", "s3cret"),
+                EnvVar::new("DB__PORT", 5432),
+                EnvVar::new("DB__HOST", "localhost <VAULT:db_password>"),
+                EnvVar::new("DB__UNKNOWN", "<VAULT:unknown>"),
+            ]
+        )
+    }
+
+    #[test]
+    fn substitute_should_reject_nested_replacements() {
+        // ARRANGE
+        let mut vars = vec![EnvVar::new("A", "<A>")];
+        let mapping = json!({ "<A>": ["a"] });
+
+        // ACT
+        let result = substitute(&mut vars, mapping.as_object().unwrap());
+
+        // ASSERT
+        assert!(matches!(result, Err(Error::InvalidInput(_))));
+        assert_eq!(vars, vec![EnvVar::new("A", "<A>")])
+    }
+}
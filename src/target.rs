@@ -0,0 +1,181 @@
+use std::{borrow::Cow, collections::HashMap, fmt::Display};
+
+use crate::{warning::Warning, EnvVar};
+
+/// Platform whose rules the variable names follow
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Target {
+    /// Letters, digits and `_`, not starting with a digit, as accepted by the shells
+    #[default]
+    Posix,
+    /// Also `(` and `)`, like `ProgramFiles(x86)`, compared regardless of their case
+    Windows,
+}
+
+impl Display for Target {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Target::Posix => write!(fmt, "POSIX"),
+            Target::Windows => write!(fmt, "Windows"),
+        }
+    }
+}
+
+impl Target {
+    fn is_valid_char(self, char: char) -> bool {
+        match self {
+            Target::Posix => char.is_ascii_alphanumeric() || char == '_',
+            Target::Windows => char.is_ascii_alphanumeric() || matches!(char, '_' | '(' | ')'),
+        }
+    }
+
+    /// Whether the name is accepted on the platform
+    pub fn is_valid_name(self, key: &str) -> bool {
+        !key.starts_with(|char: char| char.is_ascii_digit())
+            && !key.is_empty()
+            && key.chars().all(|char| self.is_valid_char(char))
+    }
+
+    /// Name accepted on the platform, replacing the invalid characters with `_` and prefixing a
+    /// leading digit with `_`
+    pub fn sanitize(self, key: &str) -> Cow<'_, str> {
+        if self.is_valid_name(key) {
+            return Cow::Borrowed(key);
+        }
+
+        let prefix = match key.starts_with(|char: char| char.is_ascii_digit()) || key.is_empty() {
+            true => "_",
+            false => "",
+        };
+        let name = key
+            .chars()
+            .map(|char| if self.is_valid_char(char) { char } else { '_' })
+            .collect::<String>();
+
+        Cow::Owned(format!("{prefix}{name}"))
+    }
+
+    /// Form of the name shared by the variables that are the same on the platform
+    fn identity(self, key: &str) -> Cow<'_, str> {
+        match self {
+            Target::Posix => Cow::Borrowed(key),
+            Target::Windows => Cow::Owned(key.to_uppercase()),
+        }
+    }
+
+    /// Report the variables that are invalid on the platform, in their order
+    pub fn validate(self, vars: &[EnvVar]) -> Vec<Warning> {
+        vars.iter()
+            .filter(|var| !self.is_valid_name(var.key()))
+            .map(|var| Warning::InvalidName {
+                key: var.key().to_owned(),
+                target: self,
+            })
+            .collect()
+    }
+
+    /// Report the variables replacing another one on the platform, like `Path` after `PATH` on
+    /// Windows
+    pub fn collisions(self, vars: &[EnvVar]) -> Vec<Warning> {
+        let mut warnings = Vec::new();
+
+        let mut seen = HashMap::with_capacity(vars.len());
+        for var in vars {
+            match seen.get(&self.identity(var.key())) {
+                Some(first) => warnings.push(Warning::Collision {
+                    key: var.key().to_owned(),
+                    sources: [*first, var.source_pointer()]
+                        .into_iter()
+                        .flatten()
+                        .map(str::to_owned)
+                        .collect(),
+                }),
+                None => {
+                    seen.insert(self.identity(var.key()), var.source_pointer());
+                }
+            }
+        }
+
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Target;
+    use crate::{warning::Warning, EnvVar};
+
+    #[test]
+    fn sanitize_should_follow_the_rules_of_the_target() {
+        // ARRANGE
+        let keys = ["DB__HOST", "ProgramFiles(x86)", "app.name", "1ST", "prix-€"];
+
+        // ACT
+        let posix = keys.map(|key| Target::Posix.sanitize(key).into_owned());
+        let windows = keys.map(|key| Target::Windows.sanitize(key).into_owned());
+
+        // ASSERT
+        assert_eq!(
+            posix,
+            [
+                "DB__HOST",
+                "ProgramFiles_x86_",
+                "app_name",
+                "_1ST",
+                "prix__"
+            ]
+        );
+        assert_eq!(
+            windows,
+            [
+                "DB__HOST",
+                "ProgramFiles(x86)",
+                "app_name",
+                "_1ST",
+                "prix__"
+            ]
+        )
+    }
+
+    #[test]
+    fn validate_should_report_the_invalid_names() {
+        // ARRANGE
+        let vars = [
+            EnvVar::new("HOST", "localhost"),
+            EnvVar::new("ProgramFiles(x86)", "C:\\Program Files (x86)"),
+        ];
+
+        // ACT
+        let posix = Target::Posix.validate(&vars);
+        let windows = Target::Windows.validate(&vars);
+
+        // ASSERT
+        let expected = Warning::InvalidName {
+            key: "ProgramFiles(x86)".to_owned(),
+            target: Target::Posix,
+        };
+        assert_eq!(posix, vec![expected]);
+        assert_eq!(windows, vec![])
+    }
+
+    #[test]
+    fn collisions_should_ignore_the_case_on_windows() {
+        // ARRANGE
+        let vars = [
+            EnvVar::new("PATH", "/bin").with_source_pointer("/PATH"),
+            EnvVar::new("Path", "/usr/bin").with_source_pointer("/Path"),
+        ];
+
+        // ACT
+        let posix = Target::Posix.collisions(&vars);
+        let windows = Target::Windows.collisions(&vars);
+
+        // ASSERT
+        let expected = Warning::Collision {
+            key: "Path".to_owned(),
+            sources: vec!["/PATH".to_owned(), "/Path".to_owned()],
+        };
+        assert_eq!(posix, vec![]);
+        assert_eq!(windows, vec![expected])
+    }
+}
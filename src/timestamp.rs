@@ -0,0 +1,143 @@
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, FixedOffset, NaiveDateTime, SecondsFormat,
+};
+use serde_json::Value;
+
+use crate::Error;
+
+/// Layouts of the timestamps with an offset recognized besides RFC 3339 and RFC 2822
+const OFFSET_LAYOUTS: [&str; 2] = ["%Y-%m-%d %H:%M:%S%.f%:z", "%Y-%m-%d %H:%M:%S%.f %z"];
+
+/// Layouts of the timestamps without an offset, read as UTC
+const NAIVE_LAYOUTS: [&str; 4] = [
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y/%m/%d %H:%M:%S%.f",
+    "%Y%m%dT%H%M%SZ",
+];
+
+/// Representation of the timestamps normalized by [`normalize`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimestampFormat {
+    /// RFC 3339, like `2024-05-01T12:30:00Z`, keeping the offset of the timestamp
+    Rfc3339,
+    /// `strftime` layout, like `%d/%m/%Y %H:%M`
+    Strftime(String),
+}
+
+impl TimestampFormat {
+    /// `strftime` format, failing if the layout holds an unknown specifier
+    pub fn strftime(layout: impl Into<String>) -> Result<Self, Error> {
+        let layout = layout.into();
+        if StrftimeItems::new(&layout).any(|item| item == Item::Error) {
+            let message = format!("`{layout}` is not a valid strftime format");
+            return Err(Error::InvalidOptions(message));
+        }
+
+        Ok(TimestampFormat::Strftime(layout))
+    }
+
+    fn format(&self, timestamp: DateTime<FixedOffset>) -> String {
+        match self {
+            TimestampFormat::Rfc3339 => timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            TimestampFormat::Strftime(layout) => timestamp.format(layout).to_string(),
+        }
+    }
+}
+
+/// Timestamp held by the text, in RFC 3339, RFC 2822 or a common date and time layout
+pub fn detect(text: &str) -> Option<DateTime<FixedOffset>> {
+    let text = text.trim();
+
+    DateTime::parse_from_rfc3339(text)
+        .or_else(|_| DateTime::parse_from_rfc2822(text))
+        .ok()
+        .or_else(|| {
+            OFFSET_LAYOUTS
+                .iter()
+                .find_map(|layout| DateTime::parse_from_str(text, layout).ok())
+        })
+        .or_else(|| {
+            NAIVE_LAYOUTS.iter().find_map(|layout| {
+                let naive = NaiveDateTime::parse_from_str(text, layout).ok()?;
+                Some(naive.and_utc().fixed_offset())
+            })
+        })
+}
+
+/// Rewrite the strings of the document holding a timestamp, see [`detect`], in the format.
+/// Dates without a time are left untouched.
+pub fn normalize(json: &mut Value, format: &TimestampFormat) {
+    let mut stack = vec![json];
+
+    while let Some(value) = stack.pop() {
+        match value {
+            Value::String(text) => {
+                if let Some(timestamp) = detect(text) {
+                    *text = format.format(timestamp);
+                }
+            }
+            Value::Array(array) => stack.extend(array.iter_mut()),
+            Value::Object(object) => stack.extend(object.values_mut()),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{normalize, TimestampFormat};
+    use crate::Error;
+
+    #[test]
+    fn normalize_should_rewrite_timestamps_in_rfc3339() {
+        // ARRANGE
+        let mut json = json!({
+            "created": "2024-05-01 12:30:00",
+            "updated": "Wed, 01 May 2024 14:30:00 +0200",
+            "events": ["2024/05/01 12:30:00.5", "20240501T123000Z"],
+            "expires": "2024-05-01T14:30:00+02:00",
+            "birthday": "2024-05-01",
+            "name": "app"
+        });
+
+        // ACT
+        normalize(&mut json, &TimestampFormat::Rfc3339);
+
+        // ASSERT
+        let expected = json!({
+            "created": "2024-05-01T12:30:00Z",
+            "updated": "2024-05-01T14:30:00+02:00",
+            "events": ["2024-05-01T12:30:00.500Z", "2024-05-01T12:30:00Z"],
+            "expires": "2024-05-01T14:30:00+02:00",
+            "birthday": "2024-05-01",
+            "name": "app"
+        });
+        assert_eq!(json, expected)
+    }
+
+    #[test]
+    fn normalize_should_use_the_strftime_format() {
+        // ARRANGE
+        let mut json = json!({ "created": "2024-05-01T12:30:00Z" });
+        let format = TimestampFormat::strftime("%d/%m/%Y %H:%M").unwrap();
+
+        // ACT
+        normalize(&mut json, &format);
+
+        // ASSERT
+        assert_eq!(json, json!({ "created": "01/05/2024 12:30" }))
+    }
+
+    #[test]
+    fn strftime_should_reject_unknown_specifiers() {
+        // ACT
+        let result = TimestampFormat::strftime("%Y-%Q");
+
+        // ASSERT
+        assert!(matches!(result, Err(Error::InvalidOptions(_))))
+    }
+}
@@ -0,0 +1,181 @@
+use serde_json::{json, Map, Value};
+
+use crate::EnvVar;
+
+/// Name of the variable written by [`manifest`]
+pub const MANIFEST: &str = "JSON2ENV_TYPES";
+
+/// Dialect of the schemas written by [`json_schema`]
+pub const SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// JSON type of a value: `string`, `number`, `bool`, `null`, `array` or `object`
+pub fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::String(_) => "string",
+        Value::Number(_) => "number",
+        Value::Bool(_) => "bool",
+        Value::Null => "null",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// The variables, each followed by a `KEY{suffix}` variable holding the [`type_name`] of its value
+pub fn companions(vars: Vec<EnvVar>, suffix: &str) -> Vec<EnvVar> {
+    vars.into_iter()
+        .flat_map(|var| {
+            let companion = EnvVar::new(format!("{}{suffix}", var.key()), type_name(var.value()));
+            [var, companion]
+        })
+        .collect()
+}
+
+/// [`MANIFEST`] variable holding a JSON object of the [`type_name`] of each variable
+pub fn manifest(vars: &[EnvVar]) -> EnvVar {
+    let types = vars
+        .iter()
+        .map(|var| (var.key().to_owned(), type_name(var.value()).into()))
+        .collect::<Map<_, _>>();
+
+    EnvVar::new(MANIFEST, Value::Object(types).to_string())
+}
+
+/// Name, [`type_name`] and source pointer of each variable, as an array of objects
+pub fn describe(vars: &[EnvVar]) -> Value {
+    vars.iter()
+        .map(|var| {
+            json!({
+                "name": var.key(),
+                "type": type_name(var.value()),
+                "pointer": var.source_pointer(),
+            })
+        })
+        .collect()
+}
+
+/// JSON Schema of an environment holding the variables, validating the string of each one against
+/// the type of its value
+pub fn json_schema(vars: &[EnvVar]) -> Value {
+    let properties = vars
+        .iter()
+        .map(|var| {
+            let mut property = json!({ "type": "string" });
+            let pattern = match var.value() {
+                Value::Number(_) => Some(r"^-?(0|[1-9][0-9]*)(\.[0-9]+)?([eE][+-]?[0-9]+)?$"),
+                Value::Bool(_) => Some("^(true|false)$"),
+                Value::Null => Some("^null$"),
+                _ => None,
+            };
+            if let Some(pattern) = pattern {
+                property["pattern"] = pattern.into();
+            }
+            if let Some(pointer) = var.source_pointer() {
+                property["description"] = format!("`{pointer}` of the document").into();
+            }
+
+            (var.key().to_owned(), property)
+        })
+        .collect::<Map<_, _>>();
+
+    json!({
+        "$schema": SCHEMA_DIALECT,
+        "type": "object",
+        "properties": properties,
+        "required": vars.iter().map(EnvVar::key).collect::<Vec<_>>(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::{companions, describe, json_schema, manifest, MANIFEST, SCHEMA_DIALECT};
+    use crate::EnvVar;
+
+    #[test]
+    fn companions_should_follow_each_variable() {
+        // ARRANGE
+        let vars = vec![
+            EnvVar::new("HOST", "localhost"),
+            EnvVar::new("PORT", 5432),
+            EnvVar::new("DEBUG", false),
+            EnvVar::new("PROXY", Value::Null),
+        ];
+
+        // ACT
+        let result = companions(vars, "__TYPE");
+
+        // ASSERT
+        assert_eq!(
+            result,
+            vec![
+                EnvVar::new("HOST", "localhost"),
+                EnvVar::new("HOST__TYPE", "string"),
+                EnvVar::new("PORT", 5432),
+                EnvVar::new("PORT__TYPE", "number"),
+                EnvVar::new("DEBUG", false),
+                EnvVar::new("DEBUG__TYPE", "bool"),
+                EnvVar::new("PROXY", Value::Null),
+                EnvVar::new("PROXY__TYPE", "null"),
+            ]
+        )
+    }
+
+    #[test]
+    fn manifest_should_map_the_keys_to_their_types() {
+        // ARRANGE
+        let vars = [EnvVar::new("HOST", "localhost"), EnvVar::new("PORT", 5432)];
+
+        // ACT
+        let result = manifest(&vars);
+
+        // ASSERT
+        assert_eq!(
+            result,
+            EnvVar::new(MANIFEST, r#"{"HOST":"string","PORT":"number"}"#)
+        )
+    }
+
+    #[test]
+    fn describe_should_list_the_variables() {
+        // ARRANGE
+        let vars = [
+            EnvVar::new("db__port", 5432).with_source_pointer("/db/port"),
+            EnvVar::new("HOME", "/root"),
+        ];
+
+        // ACT
+        let result = describe(&vars);
+
+        // ASSERT
+        let expected = json!([
+            { "name": "db__port", "type": "number", "pointer": "/db/port" },
+            { "name": "HOME", "type": "string", "pointer": null }
+        ]);
+        assert_eq!(result, expected)
+    }
+
+    #[test]
+    fn json_schema_should_validate_the_values_by_type() {
+        // ARRANGE
+        let vars = [
+            EnvVar::new("host", "localhost").with_source_pointer("/host"),
+            EnvVar::new("debug", true),
+        ];
+
+        // ACT
+        let result = json_schema(&vars);
+
+        // ASSERT
+        let expected = json!({
+            "$schema": SCHEMA_DIALECT,
+            "type": "object",
+            "properties": {
+                "host": { "type": "string", "description": "`/host` of the document" },
+                "debug": { "type": "string", "pattern": "^(true|false)$" }
+            },
+            "required": ["host", "debug"]
+        });
+        assert_eq!(result, expected)
+    }
+}
@@ -0,0 +1,303 @@
+use serde_json::{Map, Value};
+
+use crate::Error;
+
+#[derive(Debug, Clone)]
+pub struct UnflattenOptions {
+    key_separator: String,
+    infer_types: bool,
+    index_brackets: bool,
+    index_start: usize,
+    index_width: usize,
+}
+
+impl UnflattenOptions {
+    pub fn new(key_separator: String, infer_types: bool) -> Self {
+        Self {
+            key_separator,
+            infer_types,
+            index_brackets: false,
+            index_start: 0,
+            index_width: 0,
+        }
+    }
+
+    /// Only read the keys enclosed in brackets, `[0]`, as array indices
+    pub fn index_brackets(mut self, brackets: bool) -> Self {
+        self.index_brackets = brackets;
+        self
+    }
+
+    /// Read the array indices as numbered from `start`
+    pub fn index_start(mut self, start: usize) -> Self {
+        self.index_start = start;
+        self
+    }
+
+    /// Read the array indices as padded with zeros to at least `width` digits
+    pub fn index_width(mut self, width: usize) -> Self {
+        self.index_width = width;
+        self
+    }
+
+    /// Key of the element at `index` of an array
+    fn index(&self, index: usize) -> String {
+        let (index, width) = (index + self.index_start, self.index_width);
+        match self.index_brackets {
+            true => format!("[{index:0width$}]"),
+            false => format!("{index:0width$}"),
+        }
+    }
+}
+
+/// Rebuild the nested JSON document described by flat key-value pairs, the inverse of parsing.
+///
+/// Keys are split on the key separator, objects whose keys are exactly the indices `0..n` become
+/// arrays, or `[0]..[n]` if the indices are enclosed in brackets, shifted by the index start and
+/// padded to the index width. Values are kept as strings unless type inference is enabled, in
+/// which case `null`, booleans and numbers are restored. Fails if a key is used both as a value and
+/// as the parent of other keys.
+pub fn unflatten(pairs: &[(String, String)], options: &UnflattenOptions) -> Result<Value, Error> {
+    let separator = options.key_separator.as_str();
+    let mut root = Map::new();
+
+    for (key, value) in pairs {
+        let segments = match separator.is_empty() {
+            true => vec![key.as_str()],
+            false => key.split(separator).collect(),
+        };
+
+        let (last, parents) = segments
+            .split_last()
+            .expect("split yields at least one segment");
+
+        let mut node = &mut root;
+        for (depth, segment) in parents.iter().enumerate() {
+            let child = node
+                .entry(segment.to_string())
+                .or_insert_with(|| Value::Object(Map::new()));
+
+            node = match child {
+                Value::Object(object) => object,
+                _ => return Err(conflict(&segments[..=depth], separator)),
+            };
+        }
+
+        if node.get(*last).is_some_and(Value::is_object) {
+            return Err(conflict(&segments, separator));
+        }
+
+        let value = match options.infer_types {
+            true => infer_type(value),
+            false => Value::String(value.clone()),
+        };
+
+        node.insert(last.to_string(), value);
+    }
+
+    Ok(into_arrays(Value::Object(root), options))
+}
+
+/// Keep only the pairs whose key starts with `prefix`, removing it from the key
+pub fn strip_prefix(
+    pairs: impl IntoIterator<Item = (String, String)>,
+    prefix: &str,
+) -> Vec<(String, String)> {
+    pairs
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let key = key.strip_prefix(prefix)?;
+            (!key.is_empty()).then(|| (key.to_owned(), value))
+        })
+        .collect()
+}
+
+/// Read the value as a JSON scalar if it is written exactly like one, as a string otherwise
+fn infer_type(value: &str) -> Value {
+    if value.trim() != value {
+        return Value::String(value.to_owned());
+    }
+
+    match serde_json::from_str(value) {
+        Ok(value @ (Value::Null | Value::Bool(_) | Value::Number(_))) => value,
+        _ => Value::String(value.to_owned()),
+    }
+}
+
+fn conflict(segments: &[&str], separator: &str) -> Error {
+    let key = segments.join(separator);
+    Error::InvalidInput(format!("`{key}` is both a value and the parent of other keys").into())
+}
+
+/// Turn the objects indexed by consecutive numbers starting from zero into arrays, recursively
+fn into_arrays(value: Value, options: &UnflattenOptions) -> Value {
+    let Value::Object(mut object) = value else {
+        return value;
+    };
+
+    let is_array = !object.is_empty()
+        && (0..object.len()).all(|index| object.contains_key(&options.index(index)));
+
+    match is_array {
+        true => Value::Array(
+            (0..object.len())
+                .map(|index| object.remove(&options.index(index)).unwrap_or_default())
+                .map(|value| into_arrays(value, options))
+                .collect(),
+        ),
+        false => Value::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| (key, into_arrays(value, options)))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{strip_prefix, unflatten, UnflattenOptions};
+
+    fn pair(key: &str, value: &str) -> (String, String) {
+        (key.to_owned(), value.to_owned())
+    }
+
+    fn options() -> UnflattenOptions {
+        UnflattenOptions::new("__".to_owned(), false)
+    }
+
+    #[test]
+    fn unflatten_should_rebuild_nested_objects_and_arrays() {
+        // ARRANGE
+        let pairs = vec![
+            pair("db__host", "localhost"),
+            pair("hosts__1", "b"),
+            pair("hosts__0", "a"),
+            pair("name", "app"),
+        ];
+
+        // ACT
+        let result = unflatten(&pairs, &options()).unwrap();
+
+        // ASSERT
+        assert_eq!(
+            result,
+            json!({ "db": { "host": "localhost" }, "hosts": ["a", "b"], "name": "app" })
+        )
+    }
+
+    #[test]
+    fn unflatten_should_keep_sparse_indices_as_object() {
+        // ARRANGE
+        let pairs = vec![pair("items__1", "b")];
+
+        // ACT
+        let result = unflatten(&pairs, &options()).unwrap();
+
+        // ASSERT
+        assert_eq!(result, json!({ "items": { "1": "b" } }))
+    }
+
+    #[test]
+    fn unflatten_should_only_read_bracketed_indices_when_enabled() {
+        // ARRANGE
+        let pairs = vec![
+            pair("list__[1]", "b"),
+            pair("list__[0]", "a"),
+            pair("map__0", "a"),
+        ];
+        let options = options().index_brackets(true);
+
+        // ACT
+        let result = unflatten(&pairs, &options).unwrap();
+
+        // ASSERT
+        assert_eq!(result, json!({ "list": ["a", "b"], "map": { "0": "a" } }))
+    }
+
+    #[test]
+    fn unflatten_should_read_indices_from_the_index_start() {
+        // ARRANGE
+        let pairs = vec![
+            pair("list__2", "b"),
+            pair("list__1", "a"),
+            pair("map__0", "a"),
+        ];
+        let options = options().index_start(1);
+
+        // ACT
+        let result = unflatten(&pairs, &options).unwrap();
+
+        // ASSERT
+        assert_eq!(result, json!({ "list": ["a", "b"], "map": { "0": "a" } }))
+    }
+
+    #[test]
+    fn unflatten_should_read_indices_padded_to_the_index_width() {
+        // ARRANGE
+        let pairs = vec![
+            pair("list__01", "b"),
+            pair("list__00", "a"),
+            pair("map__0", "a"),
+        ];
+        let options = options().index_width(2);
+
+        // ACT
+        let result = unflatten(&pairs, &options).unwrap();
+
+        // ASSERT
+        assert_eq!(result, json!({ "list": ["a", "b"], "map": { "0": "a" } }))
+    }
+
+    #[test]
+    fn unflatten_should_fail_when_key_is_value_and_parent() {
+        // ARRANGE
+        let pairs = vec![pair("db", "x"), pair("db__host", "localhost")];
+
+        // ACT
+        let result = unflatten(&pairs, &options());
+
+        // ASSERT
+        assert!(result.is_err())
+    }
+
+    #[test]
+    fn unflatten_should_infer_scalar_types_when_enabled() {
+        // ARRANGE
+        let pairs = vec![
+            pair("flag", "true"),
+            pair("count", "42"),
+            pair("ratio", "1.5"),
+            pair("zip", "007"),
+            pair("name", "app"),
+        ];
+        let options = UnflattenOptions::new("__".to_owned(), true);
+
+        // ACT
+        let result = unflatten(&pairs, &options).unwrap();
+
+        // ASSERT
+        assert_eq!(
+            result,
+            json!({ "flag": true, "count": 42, "ratio": 1.5, "zip": "007", "name": "app" })
+        )
+    }
+
+    #[test]
+    fn strip_prefix_should_keep_and_strip_matching_keys() {
+        // ARRANGE
+        let pairs = vec![
+            pair("MYAPP__db__host", "localhost"),
+            pair("MYAPP__", "empty"),
+            pair("PATH", "/bin"),
+        ];
+
+        // ACT
+        let result = strip_prefix(pairs, "MYAPP__");
+
+        // ASSERT
+        assert_eq!(result, vec![pair("db__host", "localhost")])
+    }
+}
@@ -0,0 +1,71 @@
+use std::fmt::Display;
+
+use crate::{limit::LimitKind, lint::LintKind, secret::SecretKind, target::Target};
+
+/// Non-fatal issue found while converting a document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Warning {
+    /// An empty object or array produced no variable
+    EmptyValue { key: String },
+    /// An array containing objects or arrays was enumerated since it cannot be joined
+    ComplexArray { key: String },
+    /// Leading or trailing whitespace has been removed from the key
+    TrimmedKey { key: String },
+    /// More than one value produced the same key, only the last one is effective
+    Collision {
+        key: String,
+        /// JSON pointers of the colliding values, when known
+        sources: Vec<String>,
+    },
+    /// The name or the value of the variable looks suspicious, see [`crate::lint::lint`]
+    Lint { key: String, kind: LintKind },
+    /// The variable looks like a secret, see [`crate::secret::detect`]
+    Secret { key: String, kind: SecretKind },
+    /// The variable exceeds a limit of a platform, see [`crate::limit::check`]
+    Limit { key: String, kind: LimitKind },
+    /// The name is not accepted on the target platform, see [`crate::target::Target::validate`]
+    InvalidName { key: String, target: Target },
+}
+
+impl Warning {
+    pub fn key(&self) -> &str {
+        match self {
+            Warning::EmptyValue { key }
+            | Warning::ComplexArray { key }
+            | Warning::TrimmedKey { key }
+            | Warning::Collision { key, .. }
+            | Warning::Lint { key, .. }
+            | Warning::Secret { key, .. }
+            | Warning::Limit { key, .. }
+            | Warning::InvalidName { key, .. } => key,
+        }
+    }
+}
+
+impl Display for Warning {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Warning::EmptyValue { key } => write!(fmt, "`{key}` is empty and has been dropped"),
+            Warning::ComplexArray { key } => {
+                write!(
+                    fmt,
+                    "`{key}` contains nested values and has been enumerated"
+                )
+            }
+            Warning::TrimmedKey { key } => write!(fmt, "`{key}` has been trimmed"),
+            Warning::Collision { key, sources } => match sources.as_slice() {
+                [first, second] => write!(
+                    fmt,
+                    "`{key}` is defined more than once, by `{first}` and `{second}`"
+                ),
+                _ => write!(fmt, "`{key}` is defined more than once"),
+            },
+            Warning::Lint { key, kind } => write!(fmt, "`{key}` {kind}"),
+            Warning::Secret { key, kind } => write!(fmt, "`{key}` {kind}"),
+            Warning::Limit { key, kind } => write!(fmt, "`{key}` {kind}"),
+            Warning::InvalidName { key, target } => {
+                write!(fmt, "`{key}` is not a valid name on {target}")
+            }
+        }
+    }
+}
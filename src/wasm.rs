@@ -0,0 +1,51 @@
+use js_sys::Array;
+use wasm_bindgen::prelude::*;
+
+use crate::{input, JsonParser, ParseOptions};
+
+/// Options of the conversion, exposed to JavaScript as `ParseOptions`
+#[wasm_bindgen(js_name = ParseOptions)]
+#[derive(Debug, Clone, Default)]
+pub struct WasmOptions {
+    options: ParseOptions,
+}
+
+#[wasm_bindgen(js_class = ParseOptions)]
+impl WasmOptions {
+    /// Options of the command line tool: `__` key separator, `,` array separator, no enumeration
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[wasm_bindgen(setter = keySeparator)]
+    pub fn set_key_separator(&mut self, separator: String) {
+        self.options.key_separator = separator;
+    }
+
+    #[wasm_bindgen(setter = arraySeparator)]
+    pub fn set_array_separator(&mut self, separator: String) {
+        self.options.array_separator = separator;
+    }
+
+    #[wasm_bindgen(setter = enumerateArray)]
+    pub fn set_enumerate_array(&mut self, enumerate: bool) {
+        self.options.enumerate_array = enumerate;
+    }
+}
+
+/// Convert the JSON document to `[key, value]` pairs of strings, values are unquoted like they
+/// would be read back from the `.env` file.
+#[wasm_bindgen]
+pub fn parse(json: &str, options: &WasmOptions) -> Result<Vec<JsValue>, JsError> {
+    let json = input::parse(json)?;
+    let vars = JsonParser::new(options.options.clone()).parse_owned(json);
+
+    let pairs = vars
+        .iter()
+        .map(<(String, String)>::from)
+        .map(|(key, value)| Array::of2(&key.into(), &value.into()).into())
+        .collect();
+
+    Ok(pairs)
+}
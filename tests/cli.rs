@@ -0,0 +1,437 @@
+//! Checks of the command line, running the built binary
+#![cfg(feature = "full")]
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+};
+
+/// Run the binary in the directory with the input written to its STDIN
+fn json2env(directory: &Path, args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_json2env"))
+        .args(args)
+        .current_dir(directory)
+        .env_remove("SOURCE_DATE_EPOCH")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // the binary may fail before reading its input
+    let _ = child.stdin.take().unwrap().write_all(input.as_bytes());
+    child.wait_with_output().unwrap()
+}
+
+/// Empty directory of the test, without the configuration files of the working directory
+fn directory(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("json2env-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+
+    directory.canonicalize().unwrap()
+}
+
+fn stdout(output: &Output) -> &str {
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+/// Whether the binary rejected the options as a usage error
+fn rejected(name: &str, args: &[&str]) -> bool {
+    let output = json2env(&directory(name), args, "{}");
+    output.status.code() == Some(2) && output.stdout.is_empty()
+}
+
+#[test]
+fn lossless_should_conflict_with_the_options_it_ignores() {
+    // ACT
+    let result = rejected(
+        "lossless-exclude",
+        &["--lossless", "--exclude-value", "TODO"],
+    );
+
+    // ASSERT
+    assert!(result)
+}
+
+#[test]
+fn lossless_should_conflict_with_substitute() {
+    // ACT
+    let result = rejected(
+        "lossless-substitute",
+        &["--lossless", "--substitute", "s.json"],
+    );
+
+    // ASSERT
+    assert!(result)
+}
+
+#[test]
+fn lossless_should_be_restored_whatever_the_parse_options() {
+    // ARRANGE
+    let directory = directory("lossless-options");
+    let input =
+        r#"{"db":{"host":"localhost","port":5432},"hosts":["a","b"],"empty":{},"my-app":[]}"#;
+    let args = [
+        "--lossless",
+        "--key-case",
+        "upper",
+        "--target",
+        "posix",
+        "--sanitize",
+        "--quoting",
+        "all",
+        "--enumerate-array",
+        "--array-length",
+        "--empty-values",
+        "literal",
+        "-o",
+        ".env",
+    ];
+
+    // ACT
+    json2env(&directory, &args, input);
+    let output = json2env(&directory, &["unflatten", "-i", ".env"], "");
+
+    // ASSERT
+    let env = std::fs::read_to_string(directory.join(".env")).unwrap();
+    let restored: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(env.contains("\nHOSTS__COUNT=\"2\"\n"));
+    assert!(env.contains("\nMY_APP=\"[]\""));
+    assert_eq!(
+        restored,
+        serde_json::from_str::<serde_json::Value>(input).unwrap()
+    )
+}
+
+#[test]
+fn header_should_conflict_with_update() {
+    // ACT
+    let result = rejected("header-update", &["--header", "--update", ".env"]);
+
+    // ASSERT
+    assert!(result)
+}
+
+#[test]
+fn header_should_reject_the_registry_format() {
+    // ACT
+    let result = rejected("header-reg", &["--header", "--format", "reg"]);
+
+    // ASSERT
+    assert!(result)
+}
+
+#[test]
+fn header_should_reject_the_build_args_format() {
+    // ARRANGE
+    let args = ["--header", "--format", "build-args", "--single-line"];
+
+    // ACT
+    let result = rejected("header-build-args", &args);
+
+    // ASSERT
+    assert!(result)
+}
+
+#[test]
+fn annotate_should_conflict_with_update() {
+    // ACT
+    let result = rejected("annotate-update", &["--annotate", "--update", ".env"]);
+
+    // ASSERT
+    assert!(result)
+}
+
+#[test]
+fn aspnet_preset_should_replace_colons() {
+    // ARRANGE
+    let input = r#"{ "ConnectionStrings:Default": "Server=db" }"#;
+
+    // ACT
+    let output = json2env(&directory("aspnet"), &["--preset", "aspnet"], input);
+
+    // ASSERT
+    assert_eq!(stdout(&output), "ConnectionStrings__Default=\"Server=db\"")
+}
+
+#[test]
+fn completions_should_complete_the_presets_of_the_configuration() {
+    // ARRANGE
+    let directory = directory("completions");
+    std::fs::write(directory.join("json2env.toml"), "[presets.ourcompany]\n").unwrap();
+
+    // ACT
+    let output = json2env(&directory, &["completions", "bash"], "");
+
+    // ASSERT
+    assert!(output.status.success());
+    assert!(stdout(&output).contains("aspnet spring ourcompany"))
+}
+
+#[test]
+fn preset_should_choose_the_format_and_quoting() {
+    // ARRANGE
+    let directory = directory("preset-format");
+    let config = "[presets.ourcompany]\nformat = \"phpdotenv\"\nquoting = \"all\"\n";
+    std::fs::write(directory.join("json2env.toml"), config).unwrap();
+
+    // ACT
+    let output = json2env(
+        &directory,
+        &["--preset", "ourcompany"],
+        r#"{ "PORT": 5432 }"#,
+    );
+
+    // ASSERT
+    assert_eq!(stdout(&output), "PORT='5432'")
+}
+
+#[test]
+fn quoting_should_turn_every_value_into_a_string() {
+    // ARRANGE
+    let input = r#"{ "HOST": "localhost", "PORT": 5432 }"#;
+
+    // ACT
+    let output = json2env(&directory("quoting"), &["--quoting", "all"], input);
+
+    // ASSERT
+    assert_eq!(stdout(&output), "HOST=\"localhost\"\nPORT=\"5432\"")
+}
+
+#[test]
+fn hash_values_should_keep_the_types_readable() {
+    // ARRANGE
+    let args = ["--emit-types", "--hash-values", "sha256"];
+
+    // ACT
+    let output = json2env(&directory("hash-types"), &args, r#"{ "PORT": 5432 }"#);
+
+    // ASSERT
+    let digest = "sha256:4aeb7ad6d5d37a041c4c5ce6562bf9e3caf05a42d931cef4d9e2a60ca623194d";
+    assert_eq!(
+        stdout(&output),
+        format!("PORT=\"{digest}\"\nPORT__TYPE=\"number\"")
+    )
+}
+
+#[test]
+fn reload_should_run_again_with_absolute_paths() {
+    // ARRANGE
+    let directory = directory("reload");
+    std::fs::write(directory.join("app.json"), r#"{ "host": "localhost" }"#).unwrap();
+    std::fs::write(directory.join("json2env.toml"), "").unwrap();
+    let args = [
+        "-i",
+        "app.json",
+        "--config=json2env.toml",
+        "--key-case",
+        "upper",
+        "-f",
+        "reload",
+    ];
+
+    // ACT
+    let output = json2env(&directory, &args, "");
+
+    // ASSERT
+    let absolute = |name| directory.join(name).to_string_lossy().into_owned();
+    let command = format!(
+        "'-i' '{input}' '--config' '{config}' '--key-case' 'upper' '--format' 'export'",
+        input = absolute("app.json"),
+        config = absolute("json2env.toml"),
+    );
+    assert!(stdout(&output).starts_with("export HOST='localhost'\njson2env_reload() {\n"));
+    assert!(stdout(&output).contains(&command))
+}
+
+#[test]
+fn stream_should_conflict_with_format() {
+    // ACT
+    let result = rejected("stream-format", &["--stream", "--format", "export"]);
+
+    // ASSERT
+    assert!(result)
+}
+
+#[test]
+fn stream_should_reject_the_format_of_the_configuration() {
+    // ARRANGE
+    let directory = directory("stream-config");
+    std::fs::write(directory.join("json2env.toml"), "format = \"export\"\n").unwrap();
+
+    // ACT
+    let output = json2env(&directory, &["--stream"], r#"{ "PORT": 5432 }"#);
+
+    // ASSERT
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(stdout(&output), "")
+}
+
+#[test]
+fn update_should_conflict_with_format() {
+    // ACT
+    let result = rejected("update-format", &["--update", ".env", "--format", "export"]);
+
+    // ASSERT
+    assert!(result)
+}
+
+#[test]
+fn update_should_reject_the_format_of_the_configuration() {
+    // ARRANGE
+    let directory = directory("update-config");
+    std::fs::write(directory.join("json2env.toml"), "format = \"export\"\n").unwrap();
+    std::fs::write(directory.join(".env"), "HOST=db\n").unwrap();
+
+    // ACT
+    let output = json2env(&directory, &["--update", ".env"], r#"{ "PORT": 5432 }"#);
+
+    // ASSERT
+    let env = std::fs::read_to_string(directory.join(".env")).unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(env, "HOST=db\n")
+}
+
+#[test]
+fn split_array_should_write_the_files_like_the_output() {
+    // ARRANGE
+    let directory = directory("split-array");
+    let args = [
+        "--split-array",
+        "{i}.env",
+        "--emit-types",
+        "--quoting",
+        "all",
+    ];
+
+    // ACT
+    let output = json2env(&directory, &args, r#"[{ "PORT": 5432 }]"#);
+
+    // ASSERT
+    let env = std::fs::read_to_string(directory.join("0.env")).unwrap();
+    assert!(output.status.success());
+    assert_eq!(env, "PORT=\"5432\"\nPORT__TYPE=\"number\"")
+}
+
+#[test]
+fn split_array_should_fail_if_a_required_variable_is_missing() {
+    // ARRANGE
+    let args = ["--split-array", "{i}.env", "--require", "HOST"];
+
+    // ACT
+    let output = json2env(
+        &directory("split-array-require"),
+        &args,
+        r#"[{ "PORT": 5432 }]"#,
+    );
+
+    // ASSERT
+    assert_eq!(output.status.code(), Some(5))
+}
+
+#[test]
+fn split_by_top_level_should_write_the_files_like_the_output() {
+    // ARRANGE
+    let directory = directory("split-by-top-level");
+    let args = [
+        "--split-by-top-level",
+        "--output-dir",
+        "envs",
+        "--emit-types",
+    ];
+
+    // ACT
+    let output = json2env(&directory, &args, r#"{ "dev": { "PORT": 5432 } }"#);
+
+    // ASSERT
+    let env = std::fs::read_to_string(directory.join("envs/dev.env")).unwrap();
+    assert!(output.status.success());
+    assert_eq!(env, "PORT=5432\nPORT__TYPE=\"number\"")
+}
+
+#[test]
+fn split_by_top_level_should_fail_if_a_required_variable_is_missing() {
+    // ARRANGE
+    let args = [
+        "--split-by-top-level",
+        "--output-dir",
+        "envs",
+        "--require",
+        "NOPE",
+    ];
+
+    // ACT
+    let output = json2env(
+        &directory("split-by-top-level-require"),
+        &args,
+        r#"{ "dev": { "PORT": 5432 } }"#,
+    );
+
+    // ASSERT
+    assert_eq!(output.status.code(), Some(5))
+}
+
+#[test]
+fn readonly_should_take_the_format_of_the_configuration() {
+    // ARRANGE
+    let directory = directory("readonly-config");
+    std::fs::write(directory.join("json2env.toml"), "format = \"export\"\n").unwrap();
+
+    // ACT
+    let output = json2env(&directory, &["--readonly"], r#"{ "HOST": "db" }"#);
+
+    // ASSERT
+    assert_eq!(stdout(&output), "export HOST='db'; readonly HOST")
+}
+
+#[test]
+fn single_line_should_reject_the_formats_ignoring_it() {
+    // ACT
+    let result = rejected("single-line", &["--single-line", "--format", "export"]);
+
+    // ASSERT
+    assert!(result)
+}
+
+#[test]
+fn configuration_should_rename_cast_and_filter_the_variables() {
+    // ARRANGE
+    let directory = directory("config-filters");
+    let config = "target = \"posix\"\ncast = [\"/port=string\"]\nexclude-value = \"TODO\"\n";
+    std::fs::write(directory.join("json2env.toml"), config).unwrap();
+    let input = r#"{ "my-app": "x", "port": 5432, "secret": "TODO" }"#;
+
+    // ACT
+    let output = json2env(&directory, &["--sanitize"], input);
+
+    // ASSERT
+    assert_eq!(stdout(&output), "my_app=\"x\"\nport=\"5432\"")
+}
+
+#[test]
+fn sanitize_should_require_a_target() {
+    // ACT
+    let result = rejected("sanitize-target", &["--sanitize"]);
+
+    // ASSERT
+    assert!(result)
+}
+
+#[test]
+fn header_should_date_the_output_with_source_date_epoch() {
+    // ARRANGE
+    let mut command = Command::new(env!("CARGO_BIN_EXE_json2env"));
+    command
+        .args(["--header", "-i", "app.json"])
+        .current_dir(directory("header-date"))
+        .env("SOURCE_DATE_EPOCH", "1700000000");
+    std::fs::write(command.get_current_dir().unwrap().join("app.json"), "{}").unwrap();
+
+    // ACT
+    let output = command.output().unwrap();
+
+    // ASSERT
+    assert!(stdout(&output).contains("\n# Date: 2023-11-14T22:13:20Z\n"))
+}